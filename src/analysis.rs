@@ -0,0 +1,262 @@
+#![allow(dead_code)]
+use std::fmt::Display;
+
+use crate::{
+    board::{Board, Chip, ForcedMove, Threat},
+    bot::Bot,
+    json::{FromJson, Json, JsonError, ToJson},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnAnalysis {
+    Full,
+    Playable {
+        /// the bot's weight for the column, if it has seen the board
+        weight: Option<i16>,
+        minmax: i16,
+        /// `value_of_board` for the player to move, after the move
+        heuristic: i16,
+        wins: bool,
+    },
+}
+
+/// how every column looks to each of our engines, from the point of view of
+/// the player to move
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub board: Board,
+    pub turn: Chip,
+    pub depth: u8,
+    pub columns: [ColumnAnalysis; Board::COLUMN_LEN],
+    /// the threats of both players, red's first
+    pub threats: Vec<Threat>,
+    /// what the player to move has to play, if anything
+    pub forced: Option<ForcedMove>,
+}
+
+impl Analysis {
+    pub fn new(board: Board, bot: Option<&Bot>, depth: u8) -> Self {
+        let turn = board.turn();
+        let weights = bot.and_then(|bot| bot.weights(board));
+        let minmax_scores = board.minmax_scores(turn, turn, depth);
+
+        let columns = std::array::from_fn(|column| {
+            let Some(minmax) = minmax_scores[column] else {
+                return ColumnAnalysis::Full;
+            };
+            let mut after = board;
+            let row = after
+                .place_chip(column, turn)
+                .expect("minmax only scores available columns");
+            ColumnAnalysis::Playable {
+                weight: weights.map(|weights| weights[column]),
+                minmax,
+                heuristic: after.value_of_board(turn),
+                wins: after.winner(column, row).is_some(),
+            }
+        });
+
+        let threats = [Chip::Red, Chip::Yellow]
+            .into_iter()
+            .flat_map(|chip| board.threats(chip))
+            .collect();
+
+        Self {
+            board,
+            turn,
+            depth,
+            columns,
+            threats,
+            forced: board.forced_move(turn),
+        }
+    }
+}
+
+/// `null` for a full column
+impl ToJson for ColumnAnalysis {
+    fn to_json(&self) -> Json {
+        match self {
+            ColumnAnalysis::Full => Json::Null,
+            ColumnAnalysis::Playable {
+                weight,
+                minmax,
+                heuristic,
+                wins,
+            } => Json::object([
+                ("weight", Json::from(*weight)),
+                ("minmax", Json::from(*minmax)),
+                ("heuristic", Json::from(*heuristic)),
+                ("wins", Json::from(*wins)),
+            ]),
+        }
+    }
+}
+
+impl FromJson for ColumnAnalysis {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        let score = |json: &Json| json.as_i64().and_then(|score| score.try_into().ok());
+        if *json == Json::Null {
+            return Ok(ColumnAnalysis::Full);
+        }
+        let weight = match json.field("weight")? {
+            Json::Null => None,
+            weight => Some(
+                score(weight)
+                    .ok_or_else(|| JsonError::InvalidValue("invalid field 'weight'".to_string()))?,
+            ),
+        };
+        Ok(ColumnAnalysis::Playable {
+            weight,
+            minmax: json.field_as("minmax", score)?,
+            heuristic: json.field_as("heuristic", score)?,
+            wins: json.field_as("wins", Json::as_bool)?,
+        })
+    }
+}
+
+impl ToJson for Analysis {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("board", self.board.to_json()),
+            ("turn", self.turn.to_json()),
+            ("depth", Json::from(self.depth)),
+            (
+                "columns",
+                Json::Array(self.columns.iter().map(ToJson::to_json).collect()),
+            ),
+            (
+                "threats",
+                Json::Array(self.threats.iter().map(ToJson::to_json).collect()),
+            ),
+            (
+                "forced",
+                self.forced.as_ref().map_or(Json::Null, ToJson::to_json),
+            ),
+        ])
+    }
+}
+
+impl FromJson for Analysis {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        let columns = json
+            .field_as("columns", Json::as_array)?
+            .iter()
+            .map(ColumnAnalysis::from_json)
+            .collect::<Result<Vec<_>, _>>()?
+            .try_into()
+            .map_err(|_| JsonError::InvalidValue("expected 7 columns".to_string()))?;
+        Ok(Self {
+            board: Board::from_json(json.field("board")?)?,
+            turn: Chip::from_json(json.field("turn")?)?,
+            depth: json.field_as("depth", |depth| depth.as_usize()?.try_into().ok())?,
+            columns,
+            threats: json
+                .field_as("threats", Json::as_array)?
+                .iter()
+                .map(Threat::from_json)
+                .collect::<Result<_, _>>()?,
+            forced: match json.field("forced")? {
+                Json::Null => None,
+                forced => Some(ForcedMove::from_json(forced)?),
+            },
+        })
+    }
+}
+
+impl Display for Analysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.board)?;
+        writeln!(f)?;
+        writeln!(f, "{:?} to move, minmax depth {}", self.turn, self.depth)?;
+        writeln!(
+            f,
+            "{:>6} {:>8} {:>8} {:>10}",
+            "column", "weight", "minmax", "heuristic"
+        )?;
+        for (column, analysis) in self.columns.iter().enumerate() {
+            match analysis {
+                ColumnAnalysis::Full => writeln!(f, "{column:>6} {:>8}", "full")?,
+                ColumnAnalysis::Playable {
+                    weight,
+                    minmax,
+                    heuristic,
+                    wins,
+                } => {
+                    let weight = match weight {
+                        Some(weight) => weight.to_string(),
+                        None => "-".to_string(),
+                    };
+                    let wins = if *wins { " wins" } else { "" };
+                    writeln!(
+                        f,
+                        "{column:>6} {weight:>8} {minmax:>8} {heuristic:>10}{wins}"
+                    )?
+                }
+            }
+        }
+        for chip in [Chip::Red, Chip::Yellow] {
+            let threats: Vec<_> = self
+                .threats
+                .iter()
+                .filter(|threat| threat.chip == chip)
+                .map(|threat| {
+                    let parity = if threat.is_odd() { "odd" } else { "even" };
+                    format!("{}/{} {parity}", threat.column, threat.row)
+                })
+                .collect();
+            if !threats.is_empty() {
+                writeln!(f, "{chip:?} threats: {}", threats.join(", "))?;
+            }
+        }
+        match self.forced {
+            Some(ForcedMove::Win(column)) => writeln!(f, "{column} wins")?,
+            Some(ForcedMove::Block(column)) => writeln!(f, "{column} has to be blocked")?,
+            Some(ForcedMove::Lost) => writeln!(f, "lost, too many threats to block")?,
+            None => (),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        board::{Board, Chip, ForcedMove},
+        json::{FromJson, ToJson},
+    };
+
+    use super::{Analysis, ColumnAnalysis};
+
+    #[test]
+    fn full_and_winning_columns() {
+        let board = Board::from_moves("000000121212").unwrap();
+        let analysis = Analysis::new(board, None, 1);
+
+        assert_eq!(analysis.columns[0], ColumnAnalysis::Full);
+        assert_eq!(analysis.forced, Some(ForcedMove::Win(1)));
+        assert!(analysis
+            .threats
+            .iter()
+            .any(|threat| (threat.column, threat.row, threat.chip) == (1, 3, Chip::Red)));
+        assert!(matches!(
+            analysis.columns[1],
+            ColumnAnalysis::Playable { wins: true, .. }
+        ));
+        assert!(matches!(
+            analysis.columns[2],
+            ColumnAnalysis::Playable {
+                wins: false,
+                weight: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn json() {
+        let analysis = Analysis::new(Board::from_moves("000000121212").unwrap(), None, 1);
+        let json = analysis.to_json().to_string();
+        assert!(json.contains(r#""turn":"red","depth":1,"columns":[null,{"weight":null,"#));
+        assert_eq!(Analysis::from_json_str(&json).unwrap(), analysis);
+    }
+}
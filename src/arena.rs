@@ -0,0 +1,620 @@
+#![allow(dead_code)]
+use std::{
+    fmt::Display,
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board::{Board, Chip},
+    bot::Game,
+    json::{FromJson, Json, JsonError, ToJson},
+    player::Player,
+    stats::{MatchStats, Sprt},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MatchScore {
+    pub ties: usize,
+    pub first_wins: usize,
+    pub second_wins: usize,
+}
+
+/// plays `games` games between `first` and `second`, letting them take turns
+/// at being red. `play_game` gets the red player first and returns the winner
+pub fn alternate_sides<P: ?Sized>(
+    first: &mut P,
+    second: &mut P,
+    games: usize,
+    play_game: impl FnMut(&mut P, &mut P) -> Option<Chip>,
+) -> MatchScore {
+    play_match(first, second, games, None, play_game).score()
+}
+
+/// like `alternate_sides`, but keeping track of which colour the first player
+/// had, and stopping before `games` once `sprt` has a verdict
+pub fn play_match<P: ?Sized>(
+    first: &mut P,
+    second: &mut P,
+    games: usize,
+    sprt: Option<Sprt>,
+    mut play_game: impl FnMut(&mut P, &mut P) -> Option<Chip>,
+) -> MatchStats {
+    let mut stats = MatchStats::default();
+    let mut first_colour = Chip::Red;
+    for _ in 0..games {
+        let (red, yellow) = match first_colour {
+            Chip::Red => (&mut *first, &mut *second),
+            Chip::Yellow => (&mut *second, &mut *first),
+        };
+        stats.record(first_colour, play_game(red, yellow));
+        first_colour = first_colour.opposite();
+        stats.verdict = sprt.and_then(|sprt| sprt.verdict(&stats.total()));
+        if stats.verdict.is_some() {
+            break;
+        }
+    }
+    stats
+}
+
+impl MatchStats {
+    /// just the totals
+    pub fn score(&self) -> MatchScore {
+        let total = self.total();
+        MatchScore {
+            ties: total.draws,
+            first_wins: total.wins,
+            second_wins: total.losses,
+        }
+    }
+}
+
+/// one game between two players in this process, for `alternate_sides`.
+/// an illegal move loses
+pub fn play_game(
+    red: &mut (dyn Player + 'static),
+    yellow: &mut (dyn Player + 'static),
+) -> Option<Chip> {
+    let mut game = Game::new();
+    loop {
+        let player = match game.turn {
+            Chip::Red => &mut *red,
+            Chip::Yellow => &mut *yellow,
+        };
+        let column = player.choose_column(game.board, game.turn);
+        let Ok(placed_row) = game.board.place_chip(column, game.turn) else {
+            break Some(game.turn.opposite());
+        };
+        if game.board.winner(column, placed_row).is_some() {
+            break Some(game.turn);
+        } else if game.board.filled() {
+            break None;
+        }
+        game.next_turn();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EngineFailure {
+    Crashed,
+    TimedOut,
+    IllegalMove(String),
+}
+
+/// an engine executable speaking the protocol from `protocol.rs`
+pub struct EngineProcess {
+    command: Vec<String>,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl EngineProcess {
+    const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+    pub fn spawn(command: &[String]) -> io::Result<Self> {
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty engine command"))?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+
+        // reading happens on its own thread, so a silent engine can be timed out
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = Self {
+            command: command.to_vec(),
+            child,
+            stdin,
+            lines,
+        };
+        engine
+            .handshake()
+            .map_err(|failure| io::Error::other(format!("engine failed to start: {failure:?}")))?;
+        Ok(engine)
+    }
+
+    pub fn name(&self) -> String {
+        self.command.join(" ")
+    }
+
+    fn handshake(&mut self) -> Result<(), EngineFailure> {
+        self.send("uci")?;
+        self.wait_for("uciok", Instant::now() + Self::STARTUP_TIMEOUT)?;
+        Ok(())
+    }
+
+    fn kill(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+
+    /// starts the engine over if it has crashed since the last game
+    pub fn ensure_running(&mut self) -> io::Result<()> {
+        if let Ok(None) = self.child.try_wait() {
+            return Ok(());
+        }
+        *self = Self::spawn(&self.command)?;
+        Ok(())
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), EngineFailure> {
+        writeln!(self.stdin, "{line}")
+            .and_then(|_| self.stdin.flush())
+            .map_err(|_| EngineFailure::Crashed)
+    }
+
+    /// the first line starting with `prefix` that arrives before `deadline`
+    fn wait_for(&mut self, prefix: &str, deadline: Instant) -> Result<String, EngineFailure> {
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match self.lines.recv_timeout(timeout) {
+                Ok(line) if line.starts_with(prefix) => break Ok(line),
+                Ok(_) => continue,
+                Err(RecvTimeoutError::Timeout) => break Err(EngineFailure::TimedOut),
+                Err(RecvTimeoutError::Disconnected) => break Err(EngineFailure::Crashed),
+            }
+        }
+    }
+
+    pub fn new_game(&mut self) -> Result<(), EngineFailure> {
+        self.send("newgame")?;
+        self.send("isready")?;
+        self.wait_for("readyok", Instant::now() + Self::STARTUP_TIMEOUT)?;
+        Ok(())
+    }
+
+    /// asks for a move in the position reached by `moves`, not checking
+    /// whether it is legal
+    pub fn best_move(
+        &mut self,
+        moves: &str,
+        movetime: Duration,
+        margin: Duration,
+    ) -> Result<usize, EngineFailure> {
+        self.send(&format!("position moves {moves}"))?;
+        self.send(&format!("go movetime {}", movetime.as_millis()))?;
+        let line = self.wait_for("bestmove", Instant::now() + movetime + margin)?;
+        let column = line.trim_start_matches("bestmove").trim();
+        column
+            .parse()
+            .map_err(|_| EngineFailure::IllegalMove(column.to_string()))
+    }
+}
+
+impl Drop for EngineProcess {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        thread::sleep(Duration::from_millis(50));
+        self.kill();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Termination {
+    FourInARow,
+    FullBoard,
+    IllegalMove(Chip, String),
+    Crashed(Chip),
+    TimedOut(Chip),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    pub red: String,
+    pub yellow: String,
+    pub moves: Vec<usize>,
+    pub winner: Option<Chip>,
+    pub termination: Termination,
+}
+
+impl Display for GameRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let result = match self.winner {
+            Some(Chip::Red) => "red",
+            Some(Chip::Yellow) => "yellow",
+            None => "tie",
+        };
+        let termination = match &self.termination {
+            Termination::FourInARow => "four in a row".to_string(),
+            Termination::FullBoard => "full board".to_string(),
+            Termination::IllegalMove(chip, column) => {
+                format!("illegal move '{column}' by {chip:?}")
+            }
+            Termination::Crashed(chip) => format!("{chip:?} crashed"),
+            Termination::TimedOut(chip) => format!("{chip:?} ran out of time"),
+        };
+        let moves: String = self.moves.iter().map(|column| column.to_string()).collect();
+        writeln!(f, "red: {}", self.red)?;
+        writeln!(f, "yellow: {}", self.yellow)?;
+        writeln!(f, "result: {result}")?;
+        writeln!(f, "termination: {termination}")?;
+        writeln!(f, "moves: {moves}")
+    }
+}
+
+impl ToJson for MatchScore {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("ties", Json::from(self.ties)),
+            ("first_wins", Json::from(self.first_wins)),
+            ("second_wins", Json::from(self.second_wins)),
+        ])
+    }
+}
+
+impl FromJson for MatchScore {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        Ok(Self {
+            ties: json.field_as("ties", Json::as_usize)?,
+            first_wins: json.field_as("first_wins", Json::as_usize)?,
+            second_wins: json.field_as("second_wins", Json::as_usize)?,
+        })
+    }
+}
+
+/// `{"type": "illegal_move", "chip": "red", "move": "9"}`, `chip` and `move`
+/// only where they apply
+impl ToJson for Termination {
+    fn to_json(&self) -> Json {
+        let (kind, chip, illegal_move) = match self {
+            Termination::FourInARow => ("four_in_a_row", None, None),
+            Termination::FullBoard => ("full_board", None, None),
+            Termination::IllegalMove(chip, column) => {
+                ("illegal_move", Some(chip), Some(column.as_str()))
+            }
+            Termination::Crashed(chip) => ("crashed", Some(chip), None),
+            Termination::TimedOut(chip) => ("timed_out", Some(chip), None),
+        };
+        let mut fields = vec![("type".to_string(), Json::from(kind))];
+        if let Some(chip) = chip {
+            fields.push(("chip".to_string(), chip.to_json()));
+        }
+        if let Some(illegal_move) = illegal_move {
+            fields.push(("move".to_string(), Json::from(illegal_move)));
+        }
+        Json::Object(fields)
+    }
+}
+
+impl FromJson for Termination {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        let chip = || Chip::from_json(json.field("chip")?);
+        match json.field_as("type", Json::as_str)? {
+            "four_in_a_row" => Ok(Termination::FourInARow),
+            "full_board" => Ok(Termination::FullBoard),
+            "illegal_move" => Ok(Termination::IllegalMove(
+                chip()?,
+                json.field_as("move", Json::as_str)?.to_string(),
+            )),
+            "crashed" => Ok(Termination::Crashed(chip()?)),
+            "timed_out" => Ok(Termination::TimedOut(chip()?)),
+            kind => Err(JsonError::InvalidValue(format!(
+                "unknown termination '{kind}'"
+            ))),
+        }
+    }
+}
+
+impl ToJson for GameRecord {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("red", Json::from(self.red.as_str())),
+            ("yellow", Json::from(self.yellow.as_str())),
+            ("moves", Json::from(self.moves.clone())),
+            ("winner", Json::from(self.winner.map(|chip| chip.to_json()))),
+            ("termination", self.termination.to_json()),
+        ])
+    }
+}
+
+impl FromJson for GameRecord {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        let moves = json
+            .field_as("moves", Json::as_array)?
+            .iter()
+            .map(|column| {
+                column
+                    .as_usize()
+                    .ok_or_else(|| JsonError::InvalidValue(format!("invalid column '{column}'")))
+            })
+            .collect::<Result<_, _>>()?;
+        let winner = match json.field("winner")? {
+            Json::Null => None,
+            winner => Some(Chip::from_json(winner)?),
+        };
+        Ok(Self {
+            red: json.field_as("red", Json::as_str)?.to_string(),
+            yellow: json.field_as("yellow", Json::as_str)?.to_string(),
+            moves,
+            winner,
+            termination: Termination::from_json(json.field("termination")?)?,
+        })
+    }
+}
+
+/// referees games between two engine executables
+pub struct Arena {
+    pub movetime: Duration,
+    /// how much longer than `movetime` an engine may take before losing on time
+    pub margin: Duration,
+    /// write game records as one json object per line instead of text
+    pub json_records: bool,
+    /// stop the match as soon as this test has a verdict
+    pub sprt: Option<Sprt>,
+}
+
+impl Arena {
+    pub fn new(movetime: Duration) -> Self {
+        Self {
+            movetime,
+            margin: Duration::from_millis(200),
+            json_records: false,
+            sprt: None,
+        }
+    }
+
+    pub fn play_game(&self, red: &mut EngineProcess, yellow: &mut EngineProcess) -> GameRecord {
+        let mut record = GameRecord {
+            red: red.name(),
+            yellow: yellow.name(),
+            moves: Vec::new(),
+            winner: None,
+            termination: Termination::FullBoard,
+        };
+        let forfeit = |record: &mut GameRecord, loser: Chip, termination| {
+            record.winner = Some(loser.opposite());
+            record.termination = termination;
+        };
+
+        for (engine, chip) in [(&mut *red, Chip::Red), (&mut *yellow, Chip::Yellow)] {
+            let ready = match engine.ensure_running() {
+                Ok(()) => engine.new_game(),
+                Err(_) => Err(EngineFailure::Crashed),
+            };
+            if let Err(failure) = ready {
+                forfeit(&mut record, chip, Self::termination(chip, failure));
+                return record;
+            }
+        }
+
+        let mut board = Board::new();
+        let mut turn = Chip::Red;
+        let mut moves = String::new();
+        loop {
+            let engine = match turn {
+                Chip::Red => &mut *red,
+                Chip::Yellow => &mut *yellow,
+            };
+            let column = match engine.best_move(&moves, self.movetime, self.margin) {
+                Ok(column) => column,
+                Err(failure) => {
+                    // a late answer would otherwise be read in the next game, and
+                    // a crashed engine may not have exited yet, so it is not
+                    // restarted by `ensure_running`
+                    engine.kill();
+                    forfeit(&mut record, turn, Self::termination(turn, failure));
+                    break;
+                }
+            };
+            let Ok(row) = board.place_chip(column, turn) else {
+                let termination = Termination::IllegalMove(turn, column.to_string());
+                forfeit(&mut record, turn, termination);
+                break;
+            };
+            record.moves.push(column);
+            moves.push_str(&column.to_string());
+
+            if board.winner(column, row).is_some() {
+                record.winner = Some(turn);
+                record.termination = Termination::FourInARow;
+                break;
+            } else if board.filled() {
+                record.termination = Termination::FullBoard;
+                break;
+            }
+            turn = turn.opposite();
+        }
+        record
+    }
+
+    fn termination(chip: Chip, failure: EngineFailure) -> Termination {
+        match failure {
+            EngineFailure::Crashed => Termination::Crashed(chip),
+            EngineFailure::TimedOut => Termination::TimedOut(chip),
+            EngineFailure::IllegalMove(column) => Termination::IllegalMove(chip, column),
+        }
+    }
+
+    /// plays up to `games` games with the engines taking turns at being red,
+    /// writing every record to `records`
+    pub fn play_match(
+        &self,
+        first: &mut EngineProcess,
+        second: &mut EngineProcess,
+        games: usize,
+        records: &mut impl Write,
+    ) -> io::Result<MatchStats> {
+        let mut written = Ok(());
+        let stats = play_match(first, second, games, self.sprt, |red, yellow| {
+            let record = self.play_game(red, yellow);
+            if written.is_ok() {
+                written = match self.json_records {
+                    true => writeln!(records, "{}", record.to_json()),
+                    false => writeln!(records, "{record}"),
+                };
+            }
+            record.winner
+        });
+        written?;
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::board::Chip;
+
+    use crate::{
+        json::{FromJson, ToJson},
+        stats::{Sprt, Verdict},
+    };
+
+    use super::{
+        alternate_sides, play_match, Arena, EngineProcess, GameRecord, MatchScore, Termination,
+    };
+
+    /// a shell script engine that runs `on_go` whenever it is asked for a move
+    fn scripted_engine(on_go: &str) -> EngineProcess {
+        let script = format!(
+            "while read line; do case \"$line\" in \
+             uci) echo uciok;; isready) echo readyok;; \
+             go*) {on_go};; quit) exit;; esac; done"
+        );
+        EngineProcess::spawn(&["sh".to_string(), "-c".to_string(), script]).unwrap()
+    }
+
+    #[test]
+    fn sides_alternate() {
+        let (mut first, mut second) = ("first", "second");
+        let mut reds = Vec::new();
+        let score = alternate_sides(&mut first, &mut second, 4, |red, _| {
+            reds.push(*red);
+            Some(Chip::Red)
+        });
+        assert_eq!(reds, ["first", "second", "first", "second"]);
+        assert_eq!(
+            score,
+            MatchScore {
+                ties: 0,
+                first_wins: 2,
+                second_wins: 2
+            }
+        );
+    }
+
+    #[test]
+    fn sprt_stops_early() {
+        let (mut first, mut second) = ("first", "second");
+        let stats = play_match(
+            &mut first,
+            &mut second,
+            1000,
+            Some(Sprt::default()),
+            |red, _| {
+                Some(if *red == "first" {
+                    Chip::Red
+                } else {
+                    Chip::Yellow
+                })
+            },
+        );
+        assert_eq!(stats.verdict, Some(Verdict::FirstStronger));
+        assert!(stats.total().games() < 100);
+        assert_eq!(stats.as_red.wins, stats.as_red.games());
+        assert_eq!(stats.as_yellow.wins, stats.as_yellow.games());
+
+        // without a test, all games get played
+        let stats = play_match(&mut first, &mut second, 10, None, |_, _| None);
+        assert_eq!((stats.total().draws, stats.verdict), (10, None));
+    }
+
+    #[test]
+    fn illegal_moves_lose() {
+        let arena = Arena::new(Duration::from_millis(10));
+        let mut red = scripted_engine("echo bestmove 0");
+        let mut yellow = scripted_engine("echo bestmove 9");
+        let record = arena.play_game(&mut red, &mut yellow);
+        assert_eq!(record.winner, Some(Chip::Red));
+        assert_eq!(
+            record.termination,
+            Termination::IllegalMove(Chip::Yellow, "9".to_string())
+        );
+        assert_eq!(record.moves, [0]);
+    }
+
+    #[test]
+    fn full_columns_lose() {
+        let arena = Arena::new(Duration::from_millis(10));
+        let mut red = scripted_engine("echo bestmove 0");
+        let mut yellow = scripted_engine("echo bestmove 0");
+        let record = arena.play_game(&mut red, &mut yellow);
+        assert_eq!(record.winner, Some(Chip::Yellow));
+        assert_eq!(
+            record.termination,
+            Termination::IllegalMove(Chip::Red, "0".to_string())
+        );
+        assert_eq!(record.moves.len(), 6);
+    }
+
+    #[test]
+    fn crashes_and_timeouts_lose() {
+        let arena = Arena::new(Duration::from_millis(10));
+        let mut red = scripted_engine("exit 1");
+        let mut yellow = scripted_engine("sleep 1");
+        let record = arena.play_game(&mut red, &mut yellow);
+        assert_eq!(record.termination, Termination::Crashed(Chip::Red));
+
+        let record = arena.play_game(&mut yellow, &mut red);
+        assert_eq!(record.termination, Termination::TimedOut(Chip::Red));
+        assert_eq!(record.winner, Some(Chip::Yellow));
+    }
+
+    #[test]
+    fn record_json() {
+        let record = GameRecord {
+            red: "minmax".to_string(),
+            yellow: "bot".to_string(),
+            moves: vec![3, 3, 9],
+            winner: Some(Chip::Yellow),
+            termination: Termination::IllegalMove(Chip::Red, "9".to_string()),
+        };
+        let json = record.to_json().to_string();
+        assert_eq!(
+            json,
+            r#"{"red":"minmax","yellow":"bot","moves":[3,3,9],"winner":"yellow","termination":{"type":"illegal_move","chip":"red","move":"9"}}"#
+        );
+        assert_eq!(GameRecord::from_json_str(&json).unwrap(), record);
+    }
+}
@@ -0,0 +1,316 @@
+#![allow(dead_code)]
+use std::{
+    fs,
+    hint::black_box,
+    io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board::{Board, Chip},
+    bot::{
+        Bot, BotTrainerBoardPosition, BotTrainerGameResult, GladiatorBotTrainer, MinMaxBotTrainer,
+        Trainer,
+    },
+    json::{FromJson, Json, JsonError, ToJson},
+    neural::{Network, SelfPlayTrainer},
+    rand::Rand,
+};
+
+/// how many times a second something could be done
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub name: String,
+    /// what was counted, i.e. `chips` or `games`
+    pub unit: String,
+    pub per_second: f64,
+}
+
+/// the measurements of one run, saved as a baseline for later runs to be
+/// compared against
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub measurements: Vec<Measurement>,
+}
+
+impl Report {
+    /// how much slower a benchmark may get before it counts as a regression,
+    /// timings on a busy machine move around quite a bit
+    pub const TOLERANCE: f64 = 0.25;
+
+    pub fn get(&self, name: &str) -> Option<&Measurement> {
+        self.measurements
+            .iter()
+            .find(|measurement| measurement.name == name)
+    }
+
+    /// the relative change of every measurement against `baseline`, i.e.
+    /// `-0.2` for 20% slower, or `None` if the baseline doesn't have it
+    pub fn changes(&self, baseline: &Report) -> Vec<(&Measurement, Option<f64>)> {
+        self.measurements
+            .iter()
+            .map(|measurement| {
+                let change = baseline
+                    .get(&measurement.name)
+                    .map(|base| measurement.per_second / base.per_second - 1.0);
+                (measurement, change)
+            })
+            .collect()
+    }
+
+    /// the names of the benchmarks that got slower than `TOLERANCE` allows
+    pub fn regressions(&self, baseline: &Report) -> Vec<&str> {
+        self.changes(baseline)
+            .into_iter()
+            .filter(|(_, change)| change.is_some_and(|change| change < -Self::TOLERANCE))
+            .map(|(measurement, _)| measurement.name.as_str())
+            .collect()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, format!("{}\n", self.to_json()))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::from_json_str(&text)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+    }
+}
+
+impl ToJson for Measurement {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("name", Json::from(self.name.as_str())),
+            ("unit", Json::from(self.unit.as_str())),
+            ("per_second", Json::from(self.per_second)),
+        ])
+    }
+}
+
+impl FromJson for Measurement {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        Ok(Self {
+            name: json.field_as("name", Json::as_str)?.to_string(),
+            unit: json.field_as("unit", Json::as_str)?.to_string(),
+            per_second: json.field_as("per_second", Json::as_f64)?,
+        })
+    }
+}
+
+impl ToJson for Report {
+    fn to_json(&self) -> Json {
+        Json::object([(
+            "measurements",
+            Json::Array(self.measurements.iter().map(ToJson::to_json).collect()),
+        )])
+    }
+}
+
+impl FromJson for Report {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        let measurements = json
+            .field_as("measurements", Json::as_array)?
+            .iter()
+            .map(Measurement::from_json)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { measurements })
+    }
+}
+
+/// times benchmarks, skipping the ones that don't contain `filter`
+pub struct Bencher {
+    min_time: Duration,
+    filter: Option<String>,
+    report: Report,
+}
+
+impl Bencher {
+    pub fn new(min_time: Duration, filter: Option<String>) -> Self {
+        Self {
+            min_time,
+            filter,
+            report: Report::default(),
+        }
+    }
+
+    /// runs `batch` over and over for at least `min_time`. `batch` returns
+    /// how many `unit`s it did
+    pub fn bench(&mut self, name: &str, unit: &str, mut batch: impl FnMut() -> u64) {
+        if self
+            .filter
+            .as_ref()
+            .is_some_and(|filter| !name.contains(filter.as_str()))
+        {
+            return;
+        }
+        let started = Instant::now();
+        let mut count = 0;
+        while count == 0 || started.elapsed() < self.min_time {
+            count += batch();
+        }
+        self.report.measurements.push(Measurement {
+            name: name.to_string(),
+            unit: unit.to_string(),
+            per_second: count as f64 / started.elapsed().as_secs_f64(),
+        });
+    }
+
+    pub fn finish(self) -> Report {
+        self.report
+    }
+}
+
+/// boards from the start, the opening and the middle of a game
+fn positions() -> [Board; 3] {
+    ["", "3342", "33422510461"]
+        .map(|moves| Board::from_moves(moves).expect("should be able to play benchmark positions"))
+}
+
+/// every benchmark we have: board operations, searches, and how many games a
+/// second each trainer gets through
+pub fn run(bencher: &mut Bencher) {
+    bencher.bench("place_chip", "chips", || {
+        let mut board = Board::new();
+        for column in 0..Board::COLUMN_LEN {
+            for _ in 0..Board::ROW_LEN {
+                let _ = black_box(board.place_chip(black_box(column), board.turn()));
+            }
+        }
+        (Board::COLUMN_LEN * Board::ROW_LEN) as u64
+    });
+
+    let [_, _, middle] = positions();
+    bencher.bench("winner", "calls", || {
+        for column in 0..Board::COLUMN_LEN {
+            for row in 0..Board::ROW_LEN {
+                black_box(black_box(middle).winner(column, row));
+            }
+        }
+        (Board::COLUMN_LEN * Board::ROW_LEN) as u64
+    });
+
+    bencher.bench("value_of_board", "boards", || {
+        for board in positions() {
+            black_box(black_box(board).value_of_board(Chip::Red));
+        }
+        3
+    });
+
+    for depth in [1, 3, 5] {
+        bencher.bench(&format!("minmax_depth_{depth}"), "searches", || {
+            for board in positions() {
+                black_box(board.minmax_with_depth(board.turn(), board.turn(), depth));
+            }
+            3
+        });
+    }
+
+    let mut bot = Bot::new(50, 0x80085);
+    bencher.bench("bot_choose", "choices", || {
+        for board in positions() {
+            black_box(bot.choose(board));
+        }
+        bot.clear_played_choices();
+        3
+    });
+
+    // trainers are quiet, so their progress doesn't end up between the
+    // results. every batch starts from fresh bots, otherwise later batches
+    // would train bots with ever more memory and get slower the longer the
+    // benchmark runs
+    bencher.bench("train_game_result", "games", || {
+        let (mut red, mut yellow) = (Bot::new(50, 1), Bot::new(50, 2));
+        BotTrainerGameResult::new(&mut red, &mut yellow)
+            .quiet()
+            .start_with_iterations(10_000);
+        10_000
+    });
+    bencher.bench("train_board_position", "games", || {
+        let (mut red, mut yellow) = (Bot::new(50, 1), Bot::new(50, 2));
+        BotTrainerBoardPosition::new(&mut red, &mut yellow)
+            .quiet()
+            .start_with_iterations(5_000);
+        5_000
+    });
+    bencher.bench("train_minmax", "games", || {
+        MinMaxBotTrainer::new(&mut Bot::new(50, 1))
+            .quiet()
+            .start_with_iterations(1);
+        1
+    });
+    let arena_size = 4;
+    // every round has half the fights of the one before, down to the final
+    let fights: usize = std::iter::successors(Some(arena_size), |fights| {
+        Some(fights / 2).filter(|&fights| fights > 0)
+    })
+    .sum();
+    bencher.bench("train_gladiator", "games", || {
+        GladiatorBotTrainer::seeded(arena_size, 1)
+            .quiet()
+            .the_one_bot_to_rule_them_all(2_000);
+        fights as u64 * 2_000
+    });
+    let mut trainer = SelfPlayTrainer::new(Network::new(64, &mut Rand::new(1)), 1);
+    bencher.bench("train_self_play", "games", || {
+        trainer.train(100);
+        100
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::json::{FromJson, ToJson};
+
+    use super::{positions, Bencher, Measurement, Report};
+
+    fn measurement(name: &str, per_second: f64) -> Measurement {
+        Measurement {
+            name: name.to_string(),
+            unit: "calls".to_string(),
+            per_second,
+        }
+    }
+
+    #[test]
+    fn regressions() {
+        let baseline = Report {
+            measurements: vec![measurement("winner", 100.0), measurement("minmax", 100.0)],
+        };
+        let report = Report {
+            measurements: vec![
+                measurement("winner", 95.0),
+                measurement("minmax", 50.0),
+                measurement("new", 1.0),
+            ],
+        };
+        let changes: Vec<_> = report
+            .changes(&baseline)
+            .into_iter()
+            .map(|(_, change)| change.map(|change| (change * 100.0).round()))
+            .collect();
+        assert_eq!(changes, vec![Some(-5.0), Some(-50.0), None]);
+        assert_eq!(report.regressions(&baseline), vec!["minmax"]);
+        assert_eq!(Report::from_json(&report.to_json()).unwrap(), report);
+    }
+
+    #[test]
+    fn bencher() {
+        let mut bencher = Bencher::new(Duration::ZERO, Some("win".to_string()));
+        let mut calls = 0;
+        bencher.bench("winner", "calls", || {
+            calls += 1;
+            7
+        });
+        bencher.bench("minmax", "searches", || unreachable!("filtered out"));
+        let report = bencher.finish();
+
+        assert_eq!(calls, 1);
+        assert_eq!(report.measurements.len(), 1);
+        assert!(report.get("winner").unwrap().per_second > 0.0);
+        assert_eq!(positions()[2].chips_placed(), 11);
+    }
+}
@@ -1,4 +1,12 @@
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    evaluator::{Evaluator, Opportunities},
+    json::{FromJson, Json, JsonError, ToJson},
+};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Chip {
@@ -27,6 +35,24 @@ pub enum PlaceChipError {
     InvalidColumn,
 }
 
+/// why a sequence of moves can't be played out
+#[derive(Debug, PartialEq)]
+pub enum MovesError {
+    ColumnOccupied,
+    InvalidColumn,
+    /// a move after someone already connected four
+    GameOver,
+}
+
+impl From<PlaceChipError> for MovesError {
+    fn from(err: PlaceChipError) -> Self {
+        match err {
+            PlaceChipError::ColumnOccupied => MovesError::ColumnOccupied,
+            PlaceChipError::InvalidColumn => MovesError::InvalidColumn,
+        }
+    }
+}
+
 const fn padded_mask(count: usize, padding: usize) -> u128 {
     let mut i = 0;
     let mut result = 0;
@@ -61,6 +87,81 @@ pub enum Minmaxxing {
     Position(usize, i16),
 }
 
+/// an empty cell that would connect four for `chip`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Threat {
+    pub column: usize,
+    pub row: usize,
+    pub chip: Chip,
+}
+
+impl Threat {
+    /// on the first, third or fifth row from the bottom
+    pub fn is_odd(&self) -> bool {
+        self.row.is_multiple_of(2)
+    }
+
+    /// once the board fills up, red gets to play the odd rows and yellow the
+    /// even ones, so these are the threats that will eventually pay off
+    pub fn is_on_own_row(&self) -> bool {
+        self.is_odd() == (self.chip == Chip::Red)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ForcedMove {
+    Win(usize),
+    /// the opponent would win here next move
+    Block(usize),
+    /// the opponent can win in more than one column, none of which win for us
+    Lost,
+}
+
+/// progress of a minmax search after completing a depth
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchInfo {
+    pub depth: u8,
+    pub column: usize,
+    pub score: i16,
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+struct Search<'evaluator> {
+    nodes: u64,
+    deadline: Option<Instant>,
+    aborted: bool,
+    evaluator: &'evaluator dyn Evaluator,
+}
+
+impl<'evaluator> Search<'evaluator> {
+    const NODES_PER_DEADLINE_CHECK: u64 = 1024;
+
+    fn new(deadline: Option<Instant>) -> Self {
+        Self::with_evaluator(deadline, &Opportunities)
+    }
+
+    fn with_evaluator(deadline: Option<Instant>, evaluator: &'evaluator dyn Evaluator) -> Self {
+        Self {
+            nodes: 0,
+            deadline,
+            aborted: false,
+            evaluator,
+        }
+    }
+
+    /// counts a node, and whether the search should keep going
+    fn visit(&mut self) -> bool {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(Self::NODES_PER_DEADLINE_CHECK) {
+            if let Some(deadline) = self.deadline {
+                self.aborted |= Instant::now() >= deadline;
+            }
+        }
+        !self.aborted
+    }
+}
+
 impl Board {
     pub const COLUMN_LEN: usize = 7;
     pub const ROW_LEN: usize = 6;
@@ -74,6 +175,31 @@ impl Board {
         }
     }
 
+    /// plays out a sequence of columns, i.e. `"4453"`, starting with red
+    pub fn from_moves(moves: &str) -> Result<Self, MovesError> {
+        let columns = moves
+            .chars()
+            .map(|column| column.to_digit(10).map(|column| column as usize))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(MovesError::InvalidColumn)?;
+        Self::from_columns(columns)
+    }
+
+    /// plays out a sequence of columns, starting with red. nothing can be
+    /// played once a player connected four
+    pub fn from_columns(columns: impl IntoIterator<Item = usize>) -> Result<Self, MovesError> {
+        let mut board = Self::new();
+        let mut won = false;
+        for column in columns {
+            if won {
+                return Err(MovesError::GameOver);
+            }
+            let row = board.place_chip(column, board.turn())?;
+            won = board.winner(column, row).is_some();
+        }
+        Ok(board)
+    }
+
     pub fn place_chip(&mut self, column: usize, chip: Chip) -> Result<usize, PlaceChipError> {
         if column >= Self::COLUMN_LEN {
             return Err(PlaceChipError::InvalidColumn);
@@ -89,7 +215,7 @@ impl Board {
         Ok(row)
     }
 
-    fn chip_at(&self, column: usize, row: usize) -> Option<Chip> {
+    pub fn chip_at(&self, column: usize, row: usize) -> Option<Chip> {
         let columns = self.as_u128();
         let chips = (columns >> (Self::ROW_BITS_LEN * column)) as usize;
         let chip = ((chips) >> (Self::CHIP_BITS_LEN * row)) & mask(Self::CHIP_BITS_LEN) as usize;
@@ -118,7 +244,7 @@ impl Board {
     }
 
     const fn as_u128(&self) -> u128 {
-        (self.column_pair.0 as u128) << std::mem::size_of::<u32>() * 8
+        (self.column_pair.0 as u128) << (std::mem::size_of::<u32>() * 8)
             | (self.column_pair.1 as u128)
     }
 
@@ -129,16 +255,47 @@ impl Board {
     }
 
     const fn pair_from_u128(value: u128) -> (u64, u32) {
-        let v64 = ((value >> std::mem::size_of::<u32>() * 8) & mask(64)) as u64;
+        let v64 = ((value >> (std::mem::size_of::<u32>() * 8)) & mask(64)) as u64;
         let v32 = (value & mask(32)) as u32;
         (v64, v32)
     }
 
+    /// the representative of this board and its mirror image, along with
+    /// whether the mirror image was picked
+    pub fn canonical(&self) -> (Self, bool) {
+        let swapped = self.swap();
+        if swapped.as_pair() < self.as_pair() {
+            (swapped, true)
+        } else {
+            (*self, false)
+        }
+    }
+
+    pub fn chips_placed(&self) -> usize {
+        (self.column_pair.0.count_ones() + self.column_pair.1.count_ones()) as usize
+    }
+
+    /// whose turn it is, assuming red placed the first chip
+    pub fn turn(&self) -> Chip {
+        if self.chips_placed().is_multiple_of(2) {
+            Chip::Red
+        } else {
+            Chip::Yellow
+        }
+    }
+
     pub fn filled(&self) -> bool {
         let ones = self.column_pair.0.count_ones() + self.column_pair.1.count_ones();
         ones as usize == Self::COLUMN_LEN * Self::ROW_LEN
     }
 
+    /// whether someone connected four or there is no column left
+    pub fn is_over(&self) -> bool {
+        self.filled()
+            || (0..Self::COLUMN_LEN)
+                .any(|column| (0..Self::ROW_LEN).any(|row| self.winner(column, row).is_some()))
+    }
+
     pub fn winner(&self, column: usize, row: usize) -> Option<Chip> {
         if column >= Self::COLUMN_LEN || row >= Self::ROW_LEN {
             return None;
@@ -196,34 +353,116 @@ impl Board {
         })
     }
 
-    fn minmax_children(&self, maximizer: Chip, turn: Chip, depth: u8) -> Minmaxxing {
+    /// the cells `chip` could place in right now to connect four
+    pub fn immediate_wins(&self, chip: Chip) -> Vec<(usize, usize)> {
+        (0..Self::COLUMN_LEN)
+            .filter_map(|column| {
+                let mut board = *self;
+                let row = board.place_chip(column, chip).ok()?;
+                board.winner(column, row).map(|_| (column, row))
+            })
+            .collect()
+    }
+
+    /// whether `(column, row)` is the next cell to be filled in its column
+    pub fn is_playable(&self, column: usize, row: usize) -> bool {
+        column < Self::COLUMN_LEN
+            && row < Self::ROW_LEN
+            && self.chip_at(column, row).is_none()
+            && (row == 0 || self.chip_at(column, row - 1).is_some())
+    }
+
+    /// every empty cell where a chip of `chip` would connect four, whether it
+    /// can be played yet or not, column by column from the bottom
+    pub fn threats(&self, chip: Chip) -> Vec<Threat> {
+        let mut threats = Vec::new();
+        for column in 0..Self::COLUMN_LEN {
+            for row in 0..Self::ROW_LEN {
+                if self.chip_at(column, row).is_some() {
+                    continue;
+                }
+                let mut board = *self;
+                board.set_chip_at(column, row, chip);
+                if board.winner(column, row).is_some() {
+                    threats.push(Threat { column, row, chip });
+                }
+            }
+        }
+        threats
+    }
+
+    /// what `chip` has to play right now, if anything: its own win first,
+    /// then the only column that stops the opponent's
+    pub fn forced_move(&self, chip: Chip) -> Option<ForcedMove> {
+        if let Some(&(column, _)) = self.immediate_wins(chip).first() {
+            return Some(ForcedMove::Win(column));
+        }
+        match self.immediate_wins(chip.opposite()).as_slice() {
+            [] => None,
+            [(column, _)] => Some(ForcedMove::Block(*column)),
+            _ => Some(ForcedMove::Lost),
+        }
+    }
+
+    /// the columns `chip` can play without handing the opponent a win on the
+    /// next move, usually by filling the cell under one of its threats
+    pub fn safe_columns(&self, chip: Chip) -> [bool; Self::COLUMN_LEN] {
+        std::array::from_fn(|column| {
+            let mut board = *self;
+            board.place_chip(column, chip).is_ok()
+                && board.immediate_wins(chip.opposite()).is_empty()
+        })
+    }
+
+    /// the number of ways to play exactly `depth` more moves from here,
+    /// starting with whoever's turn it is. games that are over have no moves
+    /// left, so they only count when they end on the last one
+    pub fn perft(&self, depth: u8) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let turn = self.turn();
+        (0..Self::COLUMN_LEN)
+            .map(|column| {
+                let mut board = *self;
+                let Ok(row) = board.place_chip(column, turn) else {
+                    return 0;
+                };
+                if depth == 1 {
+                    1
+                } else if board.winner(column, row).is_some() {
+                    0
+                } else {
+                    board.perft(depth - 1)
+                }
+            })
+            .sum()
+    }
+
+    fn minmax_children(
+        &self,
+        maximizer: Chip,
+        turn: Chip,
+        depth: u8,
+        search: &mut Search<'_>,
+    ) -> Minmaxxing {
+        // anything but a forced move loses on the spot, no need to search it
+        let columns = match self.forced_move(turn) {
+            Some(ForcedMove::Win(column) | ForcedMove::Block(column)) => {
+                std::array::from_fn(|other| other == column)
+            }
+            Some(ForcedMove::Lost) | None => self.available_column_choices(),
+        };
         let children = self
-            .available_column_choices()
+            .minmax_scores_with(maximizer, turn, depth, columns, search)
             .into_iter()
             .enumerate()
-            .filter_map(|(column, available)| if available { Some(column) } else { None })
-            .map(|column| {
-                let mut board = self.clone();
-                let row = board
-                    .place_chip(column, turn)
-                    .expect("making move based on available choices");
-                (Move { column, row }, board)
-            })
-            .map(|(pos, board)| {
-                (
-                    pos.column,
-                    board.minmax_after_move(maximizer, turn.opposite(), pos, depth),
-                )
-            })
-            .map(|(column, result)| match result {
-                Minmaxxing::Position(_, v) => (column, v),
-                Minmaxxing::Result(v) => (column, v),
-            });
+            .filter_map(|(column, score)| Some((column, score?)));
 
         let chosen = if turn == maximizer {
-            children.max_by(|(_, left_score), (_, right_score)| left_score.cmp(&right_score))
+            children.max_by(|(_, left_score), (_, right_score)| left_score.cmp(right_score))
         } else {
-            children.min_by(|(_, left_score), (_, right_score)| left_score.cmp(&right_score))
+            children.min_by(|(_, left_score), (_, right_score)| left_score.cmp(right_score))
         };
 
         chosen
@@ -231,13 +470,130 @@ impl Board {
             .expect("game is not over")
     }
 
+    /// the minmax score of playing each column, or `None` if the column is full
+    pub fn minmax_scores(
+        &self,
+        maximizer: Chip,
+        turn: Chip,
+        depth: u8,
+    ) -> [Option<i16>; Self::COLUMN_LEN] {
+        self.minmax_scores_with(
+            maximizer,
+            turn,
+            depth,
+            self.available_column_choices(),
+            &mut Search::new(None),
+        )
+    }
+
+    fn minmax_scores_with(
+        &self,
+        maximizer: Chip,
+        turn: Chip,
+        depth: u8,
+        columns: [bool; Self::COLUMN_LEN],
+        search: &mut Search<'_>,
+    ) -> [Option<i16>; Self::COLUMN_LEN] {
+        std::array::from_fn(|column| {
+            if !columns[column] {
+                return None;
+            }
+            let mut board = *self;
+            let row = board
+                .place_chip(column, turn)
+                .expect("making move based on available choices");
+            let result = board.minmax_after_move(
+                maximizer,
+                turn.opposite(),
+                Move { column, row },
+                depth,
+                search,
+            );
+            match result {
+                Minmaxxing::Position(_, v) => Some(v),
+                Minmaxxing::Result(v) => Some(v),
+            }
+        })
+    }
+
+    pub const MINMAX_DEPTH: u8 = 5;
+
     pub fn minmax(&self, maximizer: Chip, turn: Chip) -> Minmaxxing {
-        const DEPTH: u8 = 5;
+        self.minmax_with_depth(maximizer, turn, Self::MINMAX_DEPTH)
+    }
+
+    pub fn minmax_with_depth(&self, maximizer: Chip, turn: Chip, depth: u8) -> Minmaxxing {
+        self.minmax_children(maximizer, turn, depth, &mut Search::new(None))
+    }
+
+    /// like `minmax_with_depth`, but positions at `depth` are scored by
+    /// `evaluator` instead of `Opportunities`
+    pub fn minmax_with_evaluator(
+        &self,
+        maximizer: Chip,
+        turn: Chip,
+        depth: u8,
+        evaluator: &dyn Evaluator,
+    ) -> Minmaxxing {
+        self.minmax_children(
+            maximizer,
+            turn,
+            depth,
+            &mut Search::with_evaluator(None, evaluator),
+        )
+    }
 
-        self.minmax_children(maximizer, turn, DEPTH)
+    /// searches one depth at a time up to `max_depth` with `evaluator`, calling
+    /// `report` after each. a depth that is still being searched when
+    /// `deadline` passes is thrown away, but depth 0 is always completed
+    pub fn minmax_deepening(
+        &self,
+        maximizer: Chip,
+        turn: Chip,
+        max_depth: u8,
+        deadline: Option<Instant>,
+        evaluator: &dyn Evaluator,
+        mut report: impl FnMut(&SearchInfo),
+    ) -> SearchInfo {
+        let started = Instant::now();
+        let mut search = Search::with_evaluator(None, evaluator);
+        let mut best = None;
+        for depth in 0..=max_depth {
+            let result = self.minmax_children(maximizer, turn, depth, &mut search);
+            if search.aborted {
+                break;
+            }
+            let Minmaxxing::Position(column, score) = result else {
+                unreachable!("minmax_children always picks a position")
+            };
+            let info = SearchInfo {
+                depth,
+                column,
+                score,
+                nodes: search.nodes,
+                elapsed: started.elapsed(),
+            };
+            report(&info);
+            best = Some(info);
+            search.deadline = deadline;
+            if score.abs() >= 1000 {
+                break;
+            }
+        }
+        best.expect("depth 0 is always completed")
     }
 
-    fn minmax_after_move(&self, maximizer: Chip, turn: Chip, pos: Move, depth: u8) -> Minmaxxing {
+    fn minmax_after_move(
+        &self,
+        maximizer: Chip,
+        turn: Chip,
+        pos: Move,
+        depth: u8,
+        search: &mut Search<'_>,
+    ) -> Minmaxxing {
+        if !search.visit() {
+            return Minmaxxing::Result(0);
+        }
         if self.filled() {
             return Minmaxxing::Result(0);
         }
@@ -250,11 +606,10 @@ impl Board {
         }
 
         if depth == 0 {
-            let value = self.value_of_board(maximizer);
-            return Minmaxxing::Result(value * 8);
+            return Minmaxxing::Result(search.evaluator.evaluate(self, maximizer));
         }
 
-        self.minmax_children(maximizer, turn, depth - 1)
+        self.minmax_children(maximizer, turn, depth - 1, search)
     }
 
     pub fn value_of_board(&self, maximizer: Chip) -> i16 {
@@ -284,7 +639,7 @@ impl Board {
             .iter()
             .map(|(column_dir, row_dir)| {
                 (0..=3)
-                    .map(|idx| (idx - 3..=idx))
+                    .map(|idx| idx - 3..=idx)
                     .map(|mut stripe| {
                         stripe.all(|idx| {
                             let (column, row) = (
@@ -315,8 +670,51 @@ impl Board {
     }
 }
 
-impl Display for Board {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// a board rendered with some of its empty cells marked for one or both players
+pub struct MarkedBoard<'board> {
+    board: &'board Board,
+    marks: &'board [(usize, usize, Chip)],
+    falling: Option<(usize, usize, Chip)>,
+}
+
+impl MarkedBoard<'_> {
+    /// also draws a chip in an empty cell, on its way down to where it lands
+    pub fn with_falling(mut self, falling: Option<(usize, usize, Chip)>) -> Self {
+        self.falling = falling;
+        self
+    }
+}
+
+impl Board {
+    pub fn with_marks<'board>(
+        &'board self,
+        marks: &'board [(usize, usize, Chip)],
+    ) -> MarkedBoard<'board> {
+        MarkedBoard {
+            board: self,
+            marks,
+            falling: None,
+        }
+    }
+
+    /// the threats of both players, ready for `with_marks`
+    pub fn threat_marks(&self) -> Vec<(usize, usize, Chip)> {
+        [Chip::Red, Chip::Yellow]
+            .into_iter()
+            .flat_map(|chip| {
+                self.threats(chip)
+                    .into_iter()
+                    .map(|threat| (threat.column, threat.row, threat.chip))
+            })
+            .collect()
+    }
+
+    fn fmt_with_marks(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        marks: &[(usize, usize, Chip)],
+        falling: Option<(usize, usize, Chip)>,
+    ) -> std::fmt::Result {
         let column_indicators = {
             let column_indicators: [_; Self::COLUMN_LEN] =
                 std::array::from_fn(|column| column.to_string());
@@ -324,12 +722,27 @@ impl Display for Board {
         };
         let rows = {
             let mut rows: [_; Self::ROW_LEN] = std::array::from_fn(|row| {
-                let columns: [_; Self::COLUMN_LEN] =
-                    std::array::from_fn(|column| match self.chip_at(column, row) {
+                let columns: [_; Self::COLUMN_LEN] = std::array::from_fn(|column| {
+                    let falling = falling
+                        .filter(|&(c, r, _)| (c, r) == (column, row))
+                        .map(|(_, _, chip)| chip);
+                    match self.chip_at(column, row).or(falling) {
                         Some(Chip::Red) => "\x1b[0;31m0\x1b[0m",
                         Some(Chip::Yellow) => "\x1b[0;33m0\x1b[0m",
-                        None => " ",
-                    });
+                        None => {
+                            let mut marked = marks
+                                .iter()
+                                .filter(|(c, r, _)| (*c, *r) == (column, row))
+                                .map(|(_, _, chip)| chip);
+                            match (marked.next(), marked.next()) {
+                                (None, _) => " ",
+                                (Some(Chip::Red), None) => "\x1b[0;31mx\x1b[0m",
+                                (Some(Chip::Yellow), None) => "\x1b[0;33mx\x1b[0m",
+                                (Some(_), Some(_)) => "x",
+                            }
+                        }
+                    }
+                });
                 format!("|{}|", columns.join("|"))
             });
             rows.reverse();
@@ -339,11 +752,305 @@ impl Display for Board {
     }
 }
 
+impl ToJson for Chip {
+    fn to_json(&self) -> Json {
+        match self {
+            Chip::Red => Json::from("red"),
+            Chip::Yellow => Json::from("yellow"),
+        }
+    }
+}
+
+impl FromJson for Chip {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        match json.as_str() {
+            Some("red") => Ok(Chip::Red),
+            Some("yellow") => Ok(Chip::Yellow),
+            _ => Err(JsonError::InvalidValue(format!("invalid chip '{json}'"))),
+        }
+    }
+}
+
+impl ToJson for Threat {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("column", Json::from(self.column)),
+            ("row", Json::from(self.row)),
+            ("chip", self.chip.to_json()),
+            ("odd", Json::from(self.is_odd())),
+        ])
+    }
+}
+
+/// `odd` is only written for other tools, it follows from the row
+impl FromJson for Threat {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        let column = json.field_as("column", Json::as_usize)?;
+        let row = json.field_as("row", Json::as_usize)?;
+        if column >= Board::COLUMN_LEN || row >= Board::ROW_LEN {
+            return Err(JsonError::InvalidValue(format!(
+                "invalid cell '{column}, {row}'"
+            )));
+        }
+        Ok(Self {
+            column,
+            row,
+            chip: Chip::from_json(json.field("chip")?)?,
+        })
+    }
+}
+
+/// `{"win": 3}`, `{"block": 3}` or `"lost"`
+impl ToJson for ForcedMove {
+    fn to_json(&self) -> Json {
+        match self {
+            ForcedMove::Win(column) => Json::object([("win", Json::from(*column))]),
+            ForcedMove::Block(column) => Json::object([("block", Json::from(*column))]),
+            ForcedMove::Lost => Json::from("lost"),
+        }
+    }
+}
+
+impl FromJson for ForcedMove {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        if json.as_str() == Some("lost") {
+            return Ok(ForcedMove::Lost);
+        }
+        let column = |key: &str| {
+            json.get(key)
+                .and_then(Json::as_usize)
+                .filter(|&column| column < Board::COLUMN_LEN)
+        };
+        match (column("win"), column("block")) {
+            (Some(column), None) => Ok(ForcedMove::Win(column)),
+            (None, Some(column)) => Ok(ForcedMove::Block(column)),
+            _ => Err(JsonError::InvalidValue(format!(
+                "invalid forced move '{json}'"
+            ))),
+        }
+    }
+}
+
+/// the grid, row by row from the top, with `"red"`, `"yellow"` or `null` per cell
+impl ToJson for Board {
+    fn to_json(&self) -> Json {
+        let rows = (0..Self::ROW_LEN)
+            .rev()
+            .map(|row| {
+                (0..Self::COLUMN_LEN)
+                    .map(|column| self.chip_at(column, row).map(|chip| chip.to_json()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        Json::from(rows)
+    }
+}
+
+/// either the grid written by `to_json`, or the moves that lead to the board
+/// as an array of columns or a string of digits like `from_moves` takes
+impl FromJson for Board {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        let invalid = |msg: &str| JsonError::InvalidValue(format!("{msg} in board '{json}'"));
+        if let Some(moves) = json.as_str() {
+            return Self::from_moves(moves).map_err(|_| invalid("illegal move"));
+        }
+        let values = json
+            .as_array()
+            .ok_or_else(|| invalid("expected an array"))?;
+        if values.iter().all(|value| value.as_f64().is_some()) {
+            let columns = values
+                .iter()
+                .map(|value| value.as_usize().ok_or_else(|| invalid("invalid column")))
+                .collect::<Result<Vec<_>, _>>()?;
+            return Self::from_columns(columns).map_err(|_| invalid("illegal move"));
+        }
+
+        let rows: Vec<_> = values
+            .iter()
+            .filter_map(Json::as_array)
+            .filter(|cells| cells.len() == Self::COLUMN_LEN)
+            .collect();
+        if rows.len() != Self::ROW_LEN || values.len() != Self::ROW_LEN {
+            return Err(invalid("expected 6 rows of 7 cells"));
+        }
+        let mut board = Self::new();
+        let mut red = 0;
+        for column in 0..Self::COLUMN_LEN {
+            // bottom up, so a chip over an empty cell is caught by the row check
+            for (row, cells) in rows.iter().rev().enumerate() {
+                let chip = match &cells[column] {
+                    Json::Null => continue,
+                    cell => Chip::from_json(cell)?,
+                };
+                match board.place_chip(column, chip) {
+                    Ok(placed) if placed == row => (),
+                    _ => return Err(invalid("floating chip")),
+                }
+                if chip == Chip::Red {
+                    red += 1;
+                }
+            }
+        }
+        let yellow = board.chips_placed() - red;
+        if red != yellow && red != yellow + 1 {
+            return Err(invalid("impossible chip counts"));
+        }
+        Ok(board)
+    }
+}
+
+impl Display for Board {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_with_marks(f, &[], None)
+    }
+}
+
+impl Display for MarkedBoard<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.board.fmt_with_marks(f, self.marks, self.falling)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::board::{mask, padded_mask, Board, Chip};
+    use crate::{
+        board::{mask, padded_mask, Board, Chip, ForcedMove, MovesError, Threat},
+        json::{FromJson, Json, ToJson},
+        rand::{Rand, Rng},
+    };
+
+    /// the number of ways to play the first moves of a game, from 0 to 10
+    /// moves. nobody can win before the 7th move, so up to there the only
+    /// sequences missing are the 7 that overflow a column
+    const PERFT: [u64; 11] = [
+        1, 7, 49, 343, 2401, 16807, 117649, 823536, 5673234, 39394572, 268031646,
+    ];
+
+    /// the rules written the slow and obvious way, to check the bit twiddling
+    /// against
+    #[derive(Clone, Default)]
+    struct Reference {
+        cells: [[Option<Chip>; Board::ROW_LEN]; Board::COLUMN_LEN],
+        placed: usize,
+    }
+
+    impl Reference {
+        fn turn(&self) -> Chip {
+            if self.placed.is_multiple_of(2) {
+                Chip::Red
+            } else {
+                Chip::Yellow
+            }
+        }
+
+        fn available(&self) -> [bool; Board::COLUMN_LEN] {
+            std::array::from_fn(|column| self.cells[column][Board::ROW_LEN - 1].is_none())
+        }
+
+        fn filled(&self) -> bool {
+            self.cells.iter().flatten().all(Option::is_some)
+        }
+
+        fn place(&mut self, column: usize) -> usize {
+            let row = self.cells[column]
+                .iter()
+                .position(Option::is_none)
+                .expect("column has room");
+            self.cells[column][row] = Some(self.turn());
+            self.placed += 1;
+            row
+        }
+
+        /// whoever has four in a row anywhere on the board
+        fn winner(&self) -> Option<Chip> {
+            let at = |column: isize, row: isize| {
+                let (column, row) = (usize::try_from(column).ok()?, usize::try_from(row).ok()?);
+                *self.cells.get(column)?.get(row)?
+            };
+            for column in 0..Board::COLUMN_LEN as isize {
+                for row in 0..Board::ROW_LEN as isize {
+                    for (column_dir, row_dir) in [(1, 0), (0, 1), (1, 1), (1, -1)] {
+                        let chips: Vec<_> = (0..4)
+                            .map(|idx| at(column + column_dir * idx, row + row_dir * idx))
+                            .collect();
+                        if chips[0].is_some() && chips.iter().all(|chip| *chip == chips[0]) {
+                            return chips[0];
+                        }
+                    }
+                }
+            }
+            None
+        }
+
+        fn perft(&self, depth: u8) -> u64 {
+            if depth == 0 {
+                return 1;
+            }
+            let available = self.available();
+            (0..Board::COLUMN_LEN)
+                .filter(|&column| available[column])
+                .map(|column| {
+                    let mut next = self.clone();
+                    next.place(column);
+                    match (depth, next.winner()) {
+                        (1, _) => 1,
+                        (_, Some(_)) => 0,
+                        _ => next.perft(depth - 1),
+                    }
+                })
+                .sum()
+        }
+    }
 
     #[test]
+    fn perft() {
+        let board = Board::new();
+        for (depth, &expected) in PERFT.iter().enumerate().take(8) {
+            assert_eq!(board.perft(depth as u8), expected, "depth {depth}");
+        }
+        assert_eq!(Reference::default().perft(5), PERFT[5]);
+
+        // a full column and wins along the way
+        let moves = "33333302020";
+        let board = Board::from_moves(moves).unwrap();
+        let mut reference = Reference::default();
+        for column in moves.chars() {
+            reference.place(column.to_digit(10).unwrap() as usize);
+        }
+        for depth in 0..5 {
+            assert_eq!(board.perft(depth), reference.perft(depth), "depth {depth}");
+        }
+    }
+
+    #[test]
+    fn rules_match_reference() {
+        let mut rand = Rand::new(0xC4);
+        for _ in 0..500 {
+            let mut board = Board::new();
+            let mut reference = Reference::default();
+            loop {
+                assert_eq!(board.available_column_choices(), reference.available());
+                assert_eq!(board.filled(), reference.filled());
+                assert_eq!(board.turn(), reference.turn());
+                let available: Vec<_> = (0..Board::COLUMN_LEN)
+                    .filter(|&column| reference.available()[column])
+                    .collect();
+                if available.is_empty() {
+                    break;
+                }
+                let column = available[rand.below(available.len())];
+                let row = board.place_chip(column, board.turn()).unwrap();
+                assert_eq!(reference.place(column), row);
+                assert_eq!(board.winner(column, row), reference.winner());
+                if reference.winner().is_some() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[allow(clippy::unusual_byte_groupings)]
     fn test_mask() {
         assert_eq!(mask(0), 0b0);
         assert_eq!(mask(1), 0b1);
@@ -399,6 +1106,102 @@ mod test {
         );
     }
 
+    #[test]
+    fn canonical() {
+        let mut board = Board::new();
+        let _ = board.place_chip(1, Chip::Red).unwrap();
+        let _ = board.place_chip(6, Chip::Yellow).unwrap();
+        let (canonical, swapped) = board.canonical();
+        let (mirrored_canonical, mirrored_swapped) = board.swap().canonical();
+        assert_eq!(canonical, mirrored_canonical);
+        assert_ne!(swapped, mirrored_swapped);
+        assert_eq!(board.chips_placed(), 2);
+        assert_eq!(board.turn(), Chip::Red);
+    }
+
+    #[test]
+    fn from_moves() {
+        let board = Board::from_moves("4453").unwrap();
+        assert_eq!(board.chip_at(4, 0), Some(Chip::Red));
+        assert_eq!(board.chip_at(4, 1), Some(Chip::Yellow));
+        assert_eq!(board.chip_at(5, 0), Some(Chip::Red));
+        assert_eq!(board.chip_at(3, 0), Some(Chip::Yellow));
+        assert!(Board::from_moves("47").is_err());
+        assert!(Board::from_moves("4a").is_err());
+        assert!(Board::from_moves("0000000").is_err());
+        // red connected four in the first column
+        assert!(Board::from_moves("0101010").unwrap().is_over());
+        assert!(!Board::from_moves("010101").unwrap().is_over());
+        assert_eq!(
+            Board::from_moves("01010101").unwrap_err(),
+            MovesError::GameOver
+        );
+    }
+
+    #[test]
+    fn marks() {
+        let board = Board::from_moves("001122").unwrap();
+        let marks = board.threat_marks();
+        assert!(marks.contains(&(3, 0, Chip::Red)));
+
+        let falling = Some((3, 5, Chip::Red));
+        let rendered = board.with_marks(&marks).with_falling(falling).to_string();
+        let rows: Vec<_> = rendered.lines().collect();
+        // the falling chip at the top, red's threat at the bottom
+        assert!(rows[1].starts_with("| | | |\x1b[0;31m0"));
+        assert!(rows[6].ends_with("|\x1b[0;31mx\x1b[0m| | | |"));
+        assert_eq!(board.to_string().lines().count(), 1 + Board::ROW_LEN);
+    }
+
+    #[test]
+    fn immediate_wins() {
+        let board = Board::from_moves("0101022").unwrap();
+        assert_eq!(board.immediate_wins(Chip::Red), vec![(0, 3)]);
+        assert_eq!(board.immediate_wins(Chip::Yellow), vec![]);
+        let board = Board::from_moves("616151").unwrap();
+        assert_eq!(board.immediate_wins(Chip::Red), vec![]);
+        assert_eq!(board.immediate_wins(Chip::Yellow), vec![(1, 3)]);
+    }
+
+    #[test]
+    fn threats() {
+        // yellow along the bottom, with red on top of it one row up
+        let mut board = Board::new();
+        for column in 0..3 {
+            board.set_chip_at(column, 0, Chip::Yellow);
+            board.set_chip_at(column, 1, Chip::Red);
+        }
+        let red = board.threats(Chip::Red);
+        assert_eq!(
+            red,
+            vec![Threat {
+                column: 3,
+                row: 1,
+                chip: Chip::Red
+            }]
+        );
+        assert!(!red[0].is_odd() && !red[0].is_on_own_row());
+        assert!(!board.is_playable(3, 1));
+        assert_eq!(board.immediate_wins(Chip::Red), vec![]);
+        assert_eq!(board.immediate_wins(Chip::Yellow), vec![(3, 0)]);
+        assert_eq!(board.forced_move(Chip::Yellow), Some(ForcedMove::Win(3)));
+        assert_eq!(board.forced_move(Chip::Red), Some(ForcedMove::Block(3)));
+
+        for row in 0..3 {
+            board.set_chip_at(6, row, Chip::Yellow);
+        }
+        assert_eq!(board.forced_move(Chip::Red), Some(ForcedMove::Lost));
+        assert_eq!(board.forced_move(Chip::Yellow), Some(ForcedMove::Win(3)));
+        assert_eq!(Board::new().forced_move(Chip::Red), None);
+        assert_eq!(Board::new().safe_columns(Chip::Red), [true; 7]);
+
+        let threat = board.threats(Chip::Yellow)[0];
+        assert_eq!(Threat::from_json(&threat.to_json()).unwrap(), threat);
+        for forced in [ForcedMove::Win(2), ForcedMove::Block(6), ForcedMove::Lost] {
+            assert_eq!(ForcedMove::from_json(&forced.to_json()).unwrap(), forced);
+        }
+    }
+
     #[test]
     fn place() {
         let mut board = Board::new();
@@ -561,4 +1364,27 @@ mod test {
             Some((Chip::Yellow, 8))
         )
     }
+
+    #[test]
+    fn json() {
+        let board = Board::from_moves("3342").unwrap();
+        let json = board.to_json();
+        assert_eq!(
+            json.as_array().unwrap()[5].to_string(),
+            r#"[null,null,"yellow","red","red",null,null]"#
+        );
+        assert_eq!(Board::from_json(&json).unwrap(), board);
+        assert_eq!(Board::from_json_str("[3,3,4,2]").unwrap(), board);
+        assert_eq!(Board::from_json(&Json::from("3342")).unwrap(), board);
+        assert!(Board::from_json_str("[0,1,0,1,0,1,0,1]").is_err());
+
+        // a chip floating over an empty cell, and yellow moving first
+        let mut rows = json.as_array().unwrap().to_vec();
+        rows.swap(4, 5);
+        assert!(Board::from_json(&Json::Array(rows)).is_err());
+        assert!(Board::from_json_str(
+            r#"[[null,null,null,null,null,null,null],[null,null,null,null,null,null,null],[null,null,null,null,null,null,null],[null,null,null,null,null,null,null],[null,null,null,null,null,null,null],["yellow",null,null,null,null,null,null]]"#
+        )
+        .is_err());
+    }
 }
@@ -0,0 +1,316 @@
+#![allow(dead_code)]
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    board::{Board, Chip, Minmaxxing, SearchInfo},
+    player::{Player, SearchLimits},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookMove {
+    pub column: usize,
+    pub score: i16,
+}
+
+/// best moves for the first plies of a game, keyed by canonical board
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpeningBook {
+    entries: Vec<(Board, BookMove)>,
+}
+
+impl OpeningBook {
+    const MAGIC: &'static [u8; 4] = b"C4BK";
+    const ENTRY_LEN: usize = std::mem::size_of::<u64>()
+        + std::mem::size_of::<u32>()
+        + std::mem::size_of::<u8>()
+        + std::mem::size_of::<i16>();
+
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// searches every position with less than `ply` chips placed with minmax
+    /// at `depth`. there is no solver, so the book is only as good as `depth`
+    pub fn generate(ply: usize, depth: u8) -> Self {
+        let mut entries = Vec::new();
+        let mut seen = HashSet::new();
+        let mut positions = vec![Board::new()];
+
+        for _ in 0..ply {
+            let mut next_positions = Vec::new();
+            for board in positions {
+                let (board, _) = board.canonical();
+                if !seen.insert(board) {
+                    continue;
+                }
+                let turn = board.turn();
+                let (column, score) = match board.minmax_with_depth(turn, turn, depth) {
+                    Minmaxxing::Result(_) => unreachable!("game is not over"),
+                    Minmaxxing::Position(column, score) => (column, score),
+                };
+                entries.push((board, BookMove { column, score }));
+
+                for (column, available) in board.available_column_choices().into_iter().enumerate()
+                {
+                    if !available {
+                        continue;
+                    }
+                    let mut child = board;
+                    let row = child
+                        .place_chip(column, turn)
+                        .expect("making move based on available choices");
+                    if child.winner(column, row).is_none() && !child.filled() {
+                        next_positions.push(child);
+                    }
+                }
+            }
+            positions = next_positions;
+        }
+
+        Self::from_entries(entries)
+    }
+
+    fn from_entries(mut entries: Vec<(Board, BookMove)>) -> Self {
+        entries.sort_by_key(|(board, _)| board.as_pair());
+        entries.dedup_by_key(|(board, _)| board.as_pair());
+        Self { entries }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn lookup(&self, board: Board) -> Option<BookMove> {
+        let (key, swapped) = board.canonical();
+        let idx = self
+            .entries
+            .binary_search_by_key(&key.as_pair(), |(board, _)| board.as_pair())
+            .ok()?;
+        let book_move = self.entries[idx].1;
+        let column = if swapped {
+            Board::COLUMN_LEN - 1 - book_move.column
+        } else {
+            book_move.column
+        };
+        Some(BookMove {
+            column,
+            ..book_move
+        })
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(Self::MAGIC)?;
+        writer.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        for (board, book_move) in &self.entries {
+            let (left, right) = board.as_pair();
+            writer.write_all(&left.to_le_bytes())?;
+            writer.write_all(&right.to_le_bytes())?;
+            writer.write_all(&[book_move.column as u8])?;
+            writer.write_all(&book_move.score.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != Self::MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an opening book file",
+            ));
+        }
+        let mut len = [0; 4];
+        reader.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len) as usize;
+
+        let mut entries = Vec::with_capacity(len);
+        let mut bytes = [0; Self::ENTRY_LEN];
+        for _ in 0..len {
+            reader.read_exact(&mut bytes)?;
+            let left = u64::from_le_bytes(bytes[0..8].try_into().expect("8 bytes"));
+            let right = u32::from_le_bytes(bytes[8..12].try_into().expect("4 bytes"));
+            let column = bytes[12] as usize;
+            if column >= Board::COLUMN_LEN {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid column '{column}' in opening book"),
+                ));
+            }
+            let score = i16::from_le_bytes([bytes[13], bytes[14]]);
+            entries.push((Board::from_pair((left, right)), BookMove { column, score }));
+        }
+        Ok(Self::from_entries(entries))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+}
+
+impl Default for OpeningBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// plays from the opening book while in it, otherwise asks `fallback`
+pub struct BookPlayer<P: Player> {
+    book: OpeningBook,
+    fallback: P,
+}
+
+impl<P: Player> BookPlayer<P> {
+    pub fn new(book: OpeningBook, fallback: P) -> Self {
+        Self { book, fallback }
+    }
+
+    pub fn fallback(&mut self) -> &mut P {
+        &mut self.fallback
+    }
+
+    /// the book was searched for whoever's turn it is on the board, so it
+    /// has nothing for the other player
+    fn lookup(&self, board: Board, turn: Chip) -> Option<BookMove> {
+        if turn != board.turn() {
+            return None;
+        }
+        self.book.lookup(board)
+    }
+}
+
+impl<P: Player> Player for BookPlayer<P> {
+    fn choose_column(&mut self, board: Board, turn: Chip) -> usize {
+        self.choose_with_score(board, turn).0
+    }
+
+    fn choose_with_score(&mut self, board: Board, turn: Chip) -> (usize, Option<i16>) {
+        match self.lookup(board, turn) {
+            Some(BookMove { column, score }) => (column, Some(score)),
+            None => self.fallback.choose_with_score(board, turn),
+        }
+    }
+
+    fn search(
+        &mut self,
+        board: Board,
+        turn: Chip,
+        limits: SearchLimits,
+        report: &mut dyn FnMut(&SearchInfo),
+    ) -> (usize, Option<i16>) {
+        match self.lookup(board, turn) {
+            Some(BookMove { column, score }) => (column, Some(score)),
+            None => self.fallback.search(board, turn, limits, report),
+        }
+    }
+
+    fn hint(&mut self, board: Board, turn: Chip) -> (usize, Option<i16>) {
+        match self.lookup(board, turn) {
+            Some(BookMove { column, score }) => (column, Some(score)),
+            None => self.fallback.hint(board, turn),
+        }
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> bool {
+        self.fallback.set_option(name, value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        board::{Board, Chip},
+        player::{player_from_spec, MinmaxPlayer, Player},
+    };
+
+    use super::{BookMove, BookPlayer, OpeningBook};
+
+    #[test]
+    fn lookup_mirrored() {
+        let mut board = Board::new();
+        let _ = board.place_chip(1, Chip::Red).unwrap();
+        let book = OpeningBook::from_entries(vec![(
+            board.canonical().0,
+            BookMove {
+                column: if board.canonical().1 { 5 } else { 1 },
+                score: 3,
+            },
+        )]);
+
+        assert_eq!(book.lookup(board).map(|v| v.column), Some(1));
+        assert_eq!(book.lookup(board.swap()).map(|v| v.column), Some(5));
+        assert_eq!(book.lookup(Board::new()), None);
+    }
+
+    #[test]
+    fn serde() {
+        let book = OpeningBook::generate(2, 1);
+        assert_eq!(book.len(), 5);
+
+        let mut bytes = Vec::new();
+        book.write_to(&mut bytes).unwrap();
+        let result = OpeningBook::read_from(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(book, result);
+    }
+
+    #[test]
+    fn player_turn() {
+        let board = Board::new();
+        let book = OpeningBook::from_entries(vec![(
+            board,
+            BookMove {
+                column: 0,
+                score: 7,
+            },
+        )]);
+        let mut player = BookPlayer::new(book, MinmaxPlayer::new(1));
+        assert_eq!(player.choose_with_score(board, Chip::Red), (0, Some(7)));
+        // the book's move is red's, yellow gets whatever minmax thinks
+        assert_eq!(
+            player.choose_with_score(board, Chip::Yellow),
+            MinmaxPlayer::new(1).choose_with_score(board, Chip::Yellow)
+        );
+    }
+
+    #[test]
+    fn spec() {
+        let path = std::env::temp_dir().join("connect-4-ai-spec.book");
+        let board = Board::new();
+        let book = OpeningBook::from_entries(vec![(
+            board,
+            BookMove {
+                column: 0,
+                score: 7,
+            },
+        )]);
+        book.save(&path).unwrap();
+        let mut player = player_from_spec(&format!("book:{}:minmax:1", path.display())).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(player.choose_with_score(board, Chip::Red), (0, Some(7)));
+        let board = Board::from_moves("3").unwrap();
+        assert_eq!(
+            player.choose_with_score(board, Chip::Yellow),
+            MinmaxPlayer::new(1).choose_with_score(board, Chip::Yellow)
+        );
+        assert!(player_from_spec("book:missing.book").is_err());
+    }
+}
@@ -1,7 +1,20 @@
 #![allow(dead_code)]
-use std::{collections::HashMap, i16};
-
-use crate::board::{Board, Chip};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    arena::{play_match, MatchScore},
+    board::{Board, Chip},
+    json::{FromJson, Json, JsonError, ToJson},
+    policy::{Schedule, SelectionPolicy},
+    rand::Rand,
+    reward::RewardConfig,
+    stats::{MatchStats, Sprt},
+};
 
 #[derive(PartialEq, Clone, Debug)]
 pub struct Choice {
@@ -32,19 +45,74 @@ impl Weight {
     }
 }
 
+/// exploration that changes over the iterations of a trainer
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Annealing {
+    schedule: Schedule,
+    /// added to the trainer's iteration, for trainers that continue where
+    /// another one stopped
+    first_iteration: usize,
+}
+
+impl Annealing {
+    fn apply(annealing: &Option<Annealing>, bot: &mut Bot, iteration: usize) {
+        if let Some(annealing) = annealing {
+            bot.anneal(&annealing.schedule, annealing.first_iteration + iteration);
+        }
+    }
+}
+
+/// what every trainer can be told besides which bots to train
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TrainerSettings {
+    annealing: Option<Annealing>,
+    rewards: RewardConfig,
+    quiet: bool,
+}
+
+/// the builders every trainer shares
+pub trait Trainer: Sized {
+    fn settings(&mut self) -> &mut TrainerSettings;
+
+    /// sets the exploration of the bots from `schedule` before every game,
+    /// counting iterations from `first_iteration`
+    fn with_schedule(mut self, schedule: Schedule, first_iteration: usize) -> Self {
+        self.settings().annealing = Some(Annealing {
+            schedule,
+            first_iteration,
+        });
+        self
+    }
+
+    /// rewards and punishes moves as `rewards` says, instead of the defaults
+    fn with_rewards(mut self, rewards: RewardConfig) -> Self {
+        self.settings().rewards = rewards;
+        self
+    }
+
+    /// doesn't print its progress
+    fn quiet(mut self) -> Self {
+        self.settings().quiet = true;
+        self
+    }
+}
+
 pub struct BotTrainerGameResult<'bot> {
     red_bot: &'bot mut Bot,
     yellow_bot: &'bot mut Bot,
+    settings: TrainerSettings,
 }
 
 pub struct BotTrainerBoardPosition<'bot> {
     red_bot: &'bot mut Bot,
     yellow_bot: &'bot mut Bot,
+    settings: TrainerSettings,
 }
 
 pub struct MinMaxBotTrainer<'bot> {
     bot: &'bot mut Bot,
     bot_turn: Chip,
+    settings: TrainerSettings,
 }
 
 struct GladiatorGame {
@@ -54,10 +122,11 @@ struct GladiatorGame {
     statistics: GameStatistics,
 }
 
-struct GameStatistics {
-    red_wins: usize,
-    yellow_wins: usize,
-    ties: usize,
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameStatistics {
+    pub red_wins: usize,
+    pub yellow_wins: usize,
+    pub ties: usize,
 }
 
 impl GameStatistics {
@@ -70,10 +139,30 @@ impl GameStatistics {
     }
 }
 
+impl ToJson for GameStatistics {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("red_wins", Json::from(self.red_wins)),
+            ("yellow_wins", Json::from(self.yellow_wins)),
+            ("ties", Json::from(self.ties)),
+        ])
+    }
+}
+
+impl FromJson for GameStatistics {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        Ok(Self {
+            red_wins: json.field_as("red_wins", Json::as_usize)?,
+            yellow_wins: json.field_as("yellow_wins", Json::as_usize)?,
+            ties: json.field_as("ties", Json::as_usize)?,
+        })
+    }
+}
+
 impl GladiatorGame {
     pub fn new(rand: &mut Rand) -> Self {
-        let red_bot = Bot::new(5, rand.next());
-        let yellow_bot = Bot::new(5, rand.next());
+        let red_bot = Bot::with_rand(5, rand.split());
+        let yellow_bot = Bot::with_rand(5, rand.split());
         let game = Game::new();
         let statistics = GameStatistics::new();
         Self {
@@ -95,8 +184,15 @@ impl GladiatorGame {
         }
     }
 
-    pub fn evaluate(mut self, iterations: usize) -> Bot {
-        for _ in 0..iterations {
+    fn evaluate(
+        mut self,
+        iterations: usize,
+        annealing: &Option<Annealing>,
+        rewards: &RewardConfig,
+    ) -> Bot {
+        for iteration in 0..iterations {
+            Annealing::apply(annealing, &mut self.red_bot, iteration);
+            Annealing::apply(annealing, &mut self.yellow_bot, iteration);
             let result = loop {
                 let player = match self.game.turn {
                     Chip::Red => &mut self.red_bot,
@@ -121,22 +217,16 @@ impl GladiatorGame {
                 self.game.next_turn();
             };
 
-            let (red, yellow) = match result {
-                GameResult::RedWon => {
-                    self.statistics.red_wins += 1;
-                    (Action::Reward(10), Action::Punish(10))
-                }
-                GameResult::YellowWon => {
-                    self.statistics.yellow_wins += 1;
-                    (Action::Punish(10), Action::Reward(10))
-                }
-                GameResult::Tie => {
-                    self.statistics.ties += 1;
-                    (Action::Punish(1), Action::Reward(1))
-                }
+            match result {
+                GameResult::RedWon => self.statistics.red_wins += 1,
+                GameResult::YellowWon => self.statistics.yellow_wins += 1,
+                GameResult::Tie => self.statistics.ties += 1,
             };
-            self.red_bot.learn_from_played_choices(red);
-            self.yellow_bot.learn_from_played_choices(yellow);
+            let winner = result.winner();
+            self.red_bot
+                .learn_from_played_choices(rewards.action(Chip::Red, winner), rewards);
+            self.yellow_bot
+                .learn_from_played_choices(rewards.action(Chip::Yellow, winner), rewards);
             self.red_bot.clear_played_choices();
             self.yellow_bot.clear_played_choices();
 
@@ -158,6 +248,49 @@ impl GladiatorGame {
 pub struct GladiatorBotTrainer {
     fights: Vec<GladiatorGame>,
     remainder: Option<Bot>,
+    settings: TrainerSettings,
+}
+
+/// plays `games` games between `bot` and `opponent`, taking turns at being
+/// red, with both playing greedily and without learning anything
+pub fn evaluate(bot: &mut Bot, opponent: &mut Bot, games: usize) -> MatchScore {
+    evaluate_match(bot, opponent, games, None).score()
+}
+
+/// like `evaluate`, but with the games split by colour and stopping early
+/// once `sprt` decides which bot is stronger
+pub fn evaluate_match(
+    bot: &mut Bot,
+    opponent: &mut Bot,
+    games: usize,
+    sprt: Option<Sprt>,
+) -> MatchStats {
+    let greedy = (bot.greedy, opponent.greedy);
+    bot.set_greedy(true);
+    opponent.set_greedy(true);
+    let stats = play_match(bot, opponent, games, sprt, |red, yellow| {
+        let mut game = Game::new();
+        loop {
+            let player = match game.turn {
+                Chip::Red => &mut *red,
+                Chip::Yellow => &mut *yellow,
+            };
+            let column = player.choose(game.board).column;
+            let placed_row = game
+                .board
+                .place_chip(column, game.turn)
+                .expect("we only place based on available positions");
+            if game.board.winner(column, placed_row).is_some() {
+                break Some(game.turn);
+            } else if game.board.filled() {
+                break None;
+            }
+            game.next_turn();
+        }
+    });
+    bot.set_greedy(greedy.0);
+    opponent.set_greedy(greedy.1);
+    stats
 }
 
 pub struct Game {
@@ -184,26 +317,61 @@ enum GameResult {
     Tie,
 }
 
+impl GameResult {
+    fn winner(&self) -> Option<Chip> {
+        match self {
+            GameResult::RedWon => Some(Chip::Red),
+            GameResult::YellowWon => Some(Chip::Yellow),
+            GameResult::Tie => None,
+        }
+    }
+}
+
+impl Trainer for GladiatorBotTrainer {
+    fn settings(&mut self) -> &mut TrainerSettings {
+        &mut self.settings
+    }
+}
+
 impl GladiatorBotTrainer {
     pub fn new(arena_size: usize) -> Self {
-        let mut rand = Rand::new(0x40523);
+        Self::seeded(arena_size, 0x40523)
+    }
+
+    pub fn seeded(arena_size: usize, seed: usize) -> Self {
+        let mut rand = Rand::new(seed);
         let fights = Vec::from_iter((0..arena_size).map(|_| GladiatorGame::new(&mut rand)));
         Self {
             fights,
             remainder: None,
+            settings: TrainerSettings::default(),
         }
     }
 
+    /// how much every gladiator explores, instead of 5
+    pub fn with_exploration(mut self, exploration: i16) -> Self {
+        for fight in &mut self.fights {
+            fight.red_bot.exploration = exploration;
+            fight.yellow_bot.exploration = exploration;
+        }
+        self
+    }
+
     pub fn the_one_bot_to_rule_them_all(mut self, iterations: usize) -> Bot {
         loop {
-            println!("evaluating {} fights...", self.fights.len());
+            if !self.settings.quiet {
+                println!("evaluating {} fights...", self.fights.len());
+            }
             let mut games = Vec::with_capacity(self.fights.len() / 2);
             std::mem::swap(&mut games, &mut self.fights);
-            let mut winners = games.into_iter().map(|v| v.evaluate(iterations));
-            loop {
-                let Some(current) = winners.next() else {
-                    break;
-                };
+            let annealing = self.settings.annealing;
+            let mut winners = games
+                .into_iter()
+                .map(|v| v.evaluate(iterations, &annealing, &self.settings.rewards));
+            if let Some(annealing) = &mut self.settings.annealing {
+                annealing.first_iteration += iterations;
+            }
+            while let Some(current) = winners.next() {
                 let Some(partner) = winners.next() else {
                     self.remainder = Some(current);
                     break;
@@ -211,7 +379,7 @@ impl GladiatorBotTrainer {
                 self.fights
                     .push(GladiatorGame::new_from_bots(current, partner));
             }
-            if self.fights.len() == 0 {
+            if self.fights.is_empty() {
                 break self
                     .remainder
                     .expect("there can only be one bot left if arena_size > 0");
@@ -220,11 +388,18 @@ impl GladiatorBotTrainer {
     }
 }
 
+impl Trainer for MinMaxBotTrainer<'_> {
+    fn settings(&mut self) -> &mut TrainerSettings {
+        &mut self.settings
+    }
+}
+
 impl<'bot> MinMaxBotTrainer<'bot> {
     pub fn new(bot: &'bot mut Bot) -> Self {
         Self {
             bot,
             bot_turn: Chip::Red,
+            settings: TrainerSettings::default(),
         }
     }
 
@@ -252,24 +427,18 @@ impl<'bot> MinMaxBotTrainer<'bot> {
             };
 
             if let Some(winner) = game.board.winner(placed_column, placed_row) {
-                let action = if winner == self.bot_turn {
-                    Action::Reward(10)
-                } else {
-                    Action::Punish(10)
-                };
-                self.bot.learn_from_played_choices(action);
+                let action = self.settings.rewards.action(self.bot_turn, Some(winner));
+                self.bot
+                    .learn_from_played_choices(action, &self.settings.rewards);
                 self.bot.clear_played_choices();
                 break match winner {
                     Chip::Red => GameResult::RedWon,
                     Chip::Yellow => GameResult::YellowWon,
                 };
             } else if game.board.filled() {
-                let action = if self.bot_turn == Chip::Red {
-                    Action::Punish(1)
-                } else {
-                    Action::Reward(1)
-                };
-                self.bot.learn_from_played_choices(action);
+                let action = self.settings.rewards.action(self.bot_turn, None);
+                self.bot
+                    .learn_from_played_choices(action, &self.settings.rewards);
                 self.bot.clear_played_choices();
                 break GameResult::Tie;
             };
@@ -279,20 +448,28 @@ impl<'bot> MinMaxBotTrainer<'bot> {
 
     pub fn start_with_iterations(mut self, iterations: usize) {
         for iteration in 1..=iterations {
-            if iteration % (iterations / 10) == 0 {
+            if !self.settings.quiet && iteration % (iterations / 10).max(1) == 0 {
                 println!("{}%", (iteration * 100) / iterations);
             }
+            Annealing::apply(&self.settings.annealing, self.bot, iteration - 1);
             self.start_match(Game::new());
             self.bot_turn = self.bot_turn.opposite();
         }
     }
 }
 
+impl Trainer for BotTrainerBoardPosition<'_> {
+    fn settings(&mut self) -> &mut TrainerSettings {
+        &mut self.settings
+    }
+}
+
 impl<'bot> BotTrainerBoardPosition<'bot> {
     pub fn new(red_bot: &'bot mut Bot, yellow_bot: &'bot mut Bot) -> Self {
         Self {
             red_bot,
             yellow_bot,
+            settings: TrainerSettings::default(),
         }
     }
 
@@ -319,21 +496,28 @@ impl<'bot> BotTrainerBoardPosition<'bot> {
                     Chip::Red => (&mut self.red_bot, &mut self.yellow_bot),
                     Chip::Yellow => (&mut self.yellow_bot, &mut self.red_bot),
                 };
-                winner.learn_from_played_choices(Action::Reward(10));
-                loser.learn_from_played_choices(Action::Punish(10));
+                let rewards = &self.settings.rewards;
+                winner
+                    .learn_from_played_choices(rewards.action(game.turn, Some(game.turn)), rewards);
+                loser.learn_from_played_choices(
+                    rewards.action(game.turn.opposite(), Some(game.turn)),
+                    rewards,
+                );
                 let game_result = match game.turn {
                     Chip::Red => GameResult::RedWon,
                     Chip::Yellow => GameResult::YellowWon,
                 };
-                winner.learn_from_board(Chip::Red, &game_result);
-                loser.learn_from_board(Chip::Yellow, &game_result);
+                winner.learn_from_board(game.turn, Some(game.turn), rewards);
+                loser.learn_from_board(game.turn.opposite(), Some(game.turn), rewards);
                 winner.clear_played_choices();
                 loser.clear_played_choices();
                 break game_result;
             } else if game.board.filled() {
                 let game_result = GameResult::Tie;
-                self.red_bot.learn_from_board(Chip::Red, &game_result);
-                self.yellow_bot.learn_from_board(Chip::Yellow, &game_result);
+                self.red_bot
+                    .learn_from_board(Chip::Red, None, &self.settings.rewards);
+                self.yellow_bot
+                    .learn_from_board(Chip::Yellow, None, &self.settings.rewards);
                 self.red_bot.clear_played_choices();
                 self.yellow_bot.clear_played_choices();
                 break game_result;
@@ -344,20 +528,29 @@ impl<'bot> BotTrainerBoardPosition<'bot> {
 
     pub fn start_with_iterations(mut self, iterations: usize) {
         for iteration in 1..=iterations {
-            if iteration % (iterations / 5) == 0 {
+            if !self.settings.quiet && iteration % (iterations / 5).max(1) == 0 {
                 println!("{}%", (iteration * 100) / iterations);
             }
+            Annealing::apply(&self.settings.annealing, self.red_bot, iteration - 1);
+            Annealing::apply(&self.settings.annealing, self.yellow_bot, iteration - 1);
             self.start_match(Game::new());
             std::mem::swap(self.red_bot, self.yellow_bot);
         }
     }
 }
 
+impl Trainer for BotTrainerGameResult<'_> {
+    fn settings(&mut self) -> &mut TrainerSettings {
+        &mut self.settings
+    }
+}
+
 impl<'bot> BotTrainerGameResult<'bot> {
     pub fn new(red_bot: &'bot mut Bot, yellow_bot: &'bot mut Bot) -> Self {
         Self {
             red_bot,
             yellow_bot,
+            settings: TrainerSettings::default(),
         }
     }
 
@@ -383,8 +576,13 @@ impl<'bot> BotTrainerGameResult<'bot> {
                     Chip::Red => (&mut self.red_bot, &mut self.yellow_bot),
                     Chip::Yellow => (&mut self.yellow_bot, &mut self.red_bot),
                 };
-                winner.learn_from_played_choices(Action::Reward(10));
-                loser.learn_from_played_choices(Action::Punish(10));
+                let rewards = &self.settings.rewards;
+                winner
+                    .learn_from_played_choices(rewards.action(game.turn, Some(game.turn)), rewards);
+                loser.learn_from_played_choices(
+                    rewards.action(game.turn.opposite(), Some(game.turn)),
+                    rewards,
+                );
                 winner.clear_played_choices();
                 loser.clear_played_choices();
                 break match game.turn {
@@ -392,8 +590,14 @@ impl<'bot> BotTrainerGameResult<'bot> {
                     Chip::Yellow => GameResult::YellowWon,
                 };
             } else if game.board.filled() {
-                self.red_bot.learn_from_played_choices(Action::Punish(1));
-                self.yellow_bot.learn_from_played_choices(Action::Reward(1));
+                self.red_bot.learn_from_played_choices(
+                    self.settings.rewards.action(Chip::Red, None),
+                    &self.settings.rewards,
+                );
+                self.yellow_bot.learn_from_played_choices(
+                    self.settings.rewards.action(Chip::Yellow, None),
+                    &self.settings.rewards,
+                );
                 self.red_bot.clear_played_choices();
                 self.yellow_bot.clear_played_choices();
                 break GameResult::Tie;
@@ -404,7 +608,7 @@ impl<'bot> BotTrainerGameResult<'bot> {
 
     pub fn start_with_iterations(mut self, iterations: usize) {
         for iteration in 1..=iterations {
-            if iteration % (iterations / 5) == 0 {
+            if !self.settings.quiet && iteration % (iterations / 5).max(1) == 0 {
                 println!("{}%", (iteration * 100) / iterations);
                 println!(
                     "red: {}, yellow: {}",
@@ -412,37 +616,25 @@ impl<'bot> BotTrainerGameResult<'bot> {
                     self.yellow_bot.memory.len()
                 );
             }
+            Annealing::apply(&self.settings.annealing, self.red_bot, iteration - 1);
+            Annealing::apply(&self.settings.annealing, self.yellow_bot, iteration - 1);
             self.start_match(Game::new());
             std::mem::swap(self.red_bot, self.yellow_bot);
         }
     }
 }
 
-/// https://en.wikipedia.org/wiki/Linear_congruential_generator
-#[derive(Clone)]
-struct Rand(usize);
-
-impl Rand {
-    pub const MODULUS: usize = 2_usize.pow(31);
-    pub const MULTIPLIER: usize = 1103515245;
-    pub const INCREMENT: usize = 12345;
-
-    pub const fn new(seed: usize) -> Self {
-        Self(seed)
-    }
-
-    pub fn next(&mut self) -> usize {
-        self.0 = (Self::MULTIPLIER * self.0 + Self::INCREMENT) % Self::MODULUS;
-        self.0
-    }
-}
-
 #[derive(Clone)]
 pub struct Bot {
     memory: HashMap<Board, Weight>,
     played_choices: [Choice; Board::COLUMN_LEN * Board::ROW_LEN / 2],
     played_choices_len: usize,
     pub exploration: i16,
+    pub policy: SelectionPolicy,
+    /// how often each column was chosen, only kept for `SelectionPolicy::Ucb`
+    visits: HashMap<Board, [u32; Board::COLUMN_LEN]>,
+    /// always plays its best column, ignoring `policy` and `exploration`
+    greedy: bool,
     pub rand: Rand,
 }
 
@@ -452,7 +644,17 @@ pub enum Action {
 }
 
 impl Bot {
+    const MAGIC: &'static [u8; 4] = b"C4BT";
+    const ENTRY_LEN: usize = std::mem::size_of::<Board>() + std::mem::size_of::<Weight>();
+    const VISITS_LEN: usize = std::mem::size_of::<u64>()
+        + std::mem::size_of::<u32>()
+        + std::mem::size_of::<u32>() * Board::COLUMN_LEN;
+
     pub fn new(exploration: i16, seed: usize) -> Self {
+        Self::with_rand(exploration, Rand::new(seed))
+    }
+
+    pub fn with_rand(exploration: i16, rand: Rand) -> Self {
         let played_choices: [Choice; Board::COLUMN_LEN * Board::ROW_LEN / 2] =
             std::array::from_fn(|_| Choice::blank());
         Self {
@@ -460,28 +662,171 @@ impl Bot {
             played_choices,
             played_choices_len: 0,
             exploration,
-            rand: Rand::new(seed),
+            policy: SelectionPolicy::default(),
+            visits: HashMap::new(),
+            greedy: false,
+            rand,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: SelectionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// sets the knob of the bot's policy to what `schedule` has for
+    /// `iteration`, that is the exploration for `SelectionPolicy::Threshold`
+    pub fn anneal(&mut self, schedule: &Schedule, iteration: usize) {
+        let value = schedule.value_at(iteration);
+        match self.policy {
+            SelectionPolicy::Threshold => self.exploration = value.round() as i16,
+            _ => self.policy.set_parameter(value),
         }
     }
 
+    /// greedy bots play their best column, breaking ties randomly
+    pub fn set_greedy(&mut self, greedy: bool) {
+        self.greedy = greedy;
+    }
+
     pub fn change_seed(&mut self, seed: usize) {
         self.rand = Rand::new(seed)
     }
 
-    fn lesson_severity_from_turn(&self, turn: usize) -> i16 {
-        let last_turn = self.played_choices_len - 1;
-        if turn == last_turn {
-            return i16::MAX;
+    /// writes the weights of every board, then the visits of every board
+    /// the bot counted them for
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(Self::MAGIC)?;
+        writer.write_all(&(self.memory.len() as u32).to_le_bytes())?;
+        for (board, weight) in &self.memory {
+            writer.write_all(&serialize_weights(board, weight))?;
+        }
+        writer.write_all(&(self.visits.len() as u32).to_le_bytes())?;
+        for (board, visits) in &self.visits {
+            let (left, right) = board.as_pair();
+            writer.write_all(&left.to_le_bytes())?;
+            writer.write_all(&right.to_le_bytes())?;
+            for visits in visits {
+                writer.write_all(&visits.to_le_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+
+    /// reads a bot written by `save`, or a file of only weights, which is how
+    /// bots were saved before they kept their visits
+    pub fn load(path: impl AsRef<Path>, exploration: i16, seed: usize) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        BufReader::new(File::open(path)?).read_to_end(&mut bytes)?;
+
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let (weights, visits) = match bytes.strip_prefix(Self::MAGIC) {
+            Some(rest) => {
+                let (weights, rest) = take_entries(rest, Self::ENTRY_LEN)
+                    .ok_or_else(|| invalid("bot file ends in the middle of its weights"))?;
+                let (visits, rest) = take_entries(rest, Self::VISITS_LEN)
+                    .ok_or_else(|| invalid("bot file ends in the middle of its visits"))?;
+                if !rest.is_empty() {
+                    return Err(invalid("bot file goes on after its visits"));
+                }
+                (weights, visits)
+            }
+            None => (bytes.as_slice(), [].as_slice()),
+        };
+
+        let entries = weights.chunks_exact(Self::ENTRY_LEN);
+        if !entries.remainder().is_empty() {
+            return Err(invalid("bot file is not a whole number of entries"));
+        }
+
+        let mut bot = Self::new(exploration, seed);
+        for entry in entries {
+            let (board, weight) =
+                deserialize_weights(entry.try_into().expect("chunks are ENTRY_LEN long"));
+            bot.memory.insert(board, weight);
+        }
+        for entry in visits.chunks_exact(Self::VISITS_LEN) {
+            let (left, rest) = entry.split_at(std::mem::size_of::<u64>());
+            let (right, rest) = rest.split_at(std::mem::size_of::<u32>());
+            let board = Board::from_pair((
+                u64::from_le_bytes(left.try_into().expect("split at the size of a u64")),
+                u32::from_le_bytes(right.try_into().expect("split at the size of a u32")),
+            ));
+            let mut visits = [0; Board::COLUMN_LEN];
+            for (visits, bytes) in visits
+                .iter_mut()
+                .zip(rest.chunks_exact(std::mem::size_of::<u32>()))
+            {
+                *visits =
+                    u32::from_le_bytes(bytes.try_into().expect("chunks are the size of a u32"));
+            }
+            bot.visits.insert(board, visits);
         }
-        let result = 0.02 * (turn as f64).powi(2);
-        result as i16
+        Ok(bot)
+    }
+
+    /// the weights the bot has for `board`, without remembering the board if it is new
+    pub fn weights(&self, board: Board) -> Option<[i16; Board::COLUMN_LEN]> {
+        if let Some(weights) = self.memory.get(&board) {
+            return Some(weights.0);
+        }
+        let mut weights = self.memory.get(&board.swap())?.0;
+        weights.reverse();
+        Some(weights)
+    }
+
+    /// how often the bot picked each column of `board`, which it only keeps
+    /// track of with `SelectionPolicy::Ucb`
+    pub fn visits(&self, board: Board) -> Option<[u32; Board::COLUMN_LEN]> {
+        if let Some(visits) = self.visits.get(&board) {
+            return Some(*visits);
+        }
+        let mut visits = *self.visits.get(&board.swap())?;
+        visits.reverse();
+        Some(visits)
+    }
+
+    /// replaces the weights the bot has for `board`, or its mirror image if
+    /// that is the one it remembers
+    pub fn set_weights(&mut self, board: Board, mut weights: [i16; Board::COLUMN_LEN]) {
+        let (weight, swapped) = self.get_or_insert_memory_weights(board);
+        if swapped {
+            weights.reverse();
+        }
+        weight.0 = weights;
+    }
+
+    /// replaces how often the bot picked each column of `board`, kept with the
+    /// mirror image if that is the one it remembers
+    pub fn set_visits(&mut self, board: Board, mut visits: [u32; Board::COLUMN_LEN]) {
+        let key = if !self.memory.contains_key(&board) && self.memory.contains_key(&board.swap()) {
+            visits.reverse();
+            board.swap()
+        } else {
+            board
+        };
+        self.visits.insert(key, visits);
+    }
+
+    /// every board the bot remembers and its weights, a board or its mirror
+    /// image, whichever the bot saw first
+    pub fn memory(&self) -> impl Iterator<Item = (Board, [i16; Board::COLUMN_LEN])> + '_ {
+        self.memory.iter().map(|(board, weight)| (*board, weight.0))
+    }
+
+    pub fn memory_len(&self) -> usize {
+        self.memory.len()
     }
 
     pub fn clear_played_choices(&mut self) {
         self.played_choices_len = 0;
     }
 
-    fn learn_from_board(&mut self, bot_chip: Chip, game_result: &GameResult) {
+    /// every move learns the value of the board it was played on, except the
+    /// last one, which gets the final weight `rewards` has for the result
+    fn learn_from_board(&mut self, bot_chip: Chip, winner: Option<Chip>, rewards: &RewardConfig) {
+        let final_weight = rewards.final_weight(&rewards.action(bot_chip, winner));
         for idx in 0..self.played_choices_len {
             let last_turn = self.played_choices_len - 1;
             let Choice { column, board } = self.played_choices[idx];
@@ -494,16 +839,8 @@ impl Bot {
 
             let weight = &mut weights.0[column];
             if idx == last_turn {
-                match game_result {
-                    GameResult::RedWon => match bot_chip {
-                        Chip::Red => *weight = i16::MAX,
-                        Chip::Yellow => *weight = i16::MIN,
-                    },
-                    GameResult::YellowWon => match bot_chip {
-                        Chip::Red => *weight = i16::MIN,
-                        Chip::Yellow => *weight = i16::MAX,
-                    },
-                    GameResult::Tie => (),
+                if let Some(final_weight) = final_weight {
+                    *weight = final_weight;
                 }
                 continue;
             }
@@ -519,10 +856,14 @@ impl Bot {
         }
     }
 
-    pub fn learn_from_played_choices(&mut self, action: Action) {
+    /// `action` is discounted and made more severe per move as `rewards` says,
+    /// and the last move gets the final weight `rewards` has for it, if any
+    pub fn learn_from_played_choices(&mut self, action: Action, rewards: &RewardConfig) {
+        let last_turn = self.played_choices_len.saturating_sub(1);
+        let final_weight = rewards.final_weight(&action);
         for idx in 0..self.played_choices_len {
             let Choice { column, board } = self.played_choices[idx];
-            let lesson_severity = self.lesson_severity_from_turn(idx);
+            let lesson_severity = rewards.severity.at(idx);
             let (weights, swapped) = self.get_or_insert_memory_weights(board);
             let column = if swapped {
                 Board::COLUMN_LEN - 1 - column
@@ -530,29 +871,29 @@ impl Bot {
                 column
             };
             let weight = &mut weights.0[column];
+            if let (true, Some(final_weight)) = (idx == last_turn, final_weight) {
+                *weight = final_weight;
+                continue;
+            }
             match action {
                 Action::Reward(base) => {
-                    if lesson_severity == i16::MAX {
-                        *weight = i16::MAX
+                    let base = rewards.discounted(base, last_turn - idx);
+                    if let Some(new_weight) =
+                        weight.checked_add(lesson_severity.saturating_add(base))
+                    {
+                        *weight = new_weight;
                     } else {
-                        if let Some(new_weight) = weight.checked_add(lesson_severity + base as i16)
-                        {
-                            *weight = new_weight;
-                        } else {
-                            *weight = i16::MAX;
-                        }
+                        *weight = i16::MAX;
                     }
                 }
                 Action::Punish(base) => {
-                    if lesson_severity == i16::MAX {
-                        *weight = i16::MIN
+                    let base = rewards.discounted(base, last_turn - idx);
+                    if let Some(new_weight) =
+                        weight.checked_sub(lesson_severity.saturating_add(base))
+                    {
+                        *weight = new_weight;
                     } else {
-                        if let Some(new_weight) = weight.checked_sub(lesson_severity + base as i16)
-                        {
-                            *weight = new_weight;
-                        } else {
-                            *weight = i16::MIN;
-                        }
+                        *weight = i16::MIN;
                     }
                 }
             };
@@ -582,7 +923,10 @@ impl Bot {
     }
 
     pub fn choose(&mut self, board: Board) -> Choice {
-        let exploration = self.exploration;
+        let (policy, exploration) = match self.greedy {
+            true => (SelectionPolicy::Threshold, 0),
+            false => (self.policy, self.exploration),
+        };
         let (weights, swapped) = self.get_or_insert_memory_weights(board);
         let weights: Box<dyn Iterator<Item = i16>> = if swapped {
             Box::new(weights.0.into_iter().rev())
@@ -603,26 +947,25 @@ impl Bot {
             })
             .collect();
 
-        let (_, max_weight) = available_choices
-            .iter()
-            .max_by(|(_, left), (_, right)| left.cmp(right))
-            .expect("game is not tied");
-
-        let mut available_choices: Vec<_> = available_choices
-            .iter()
-            .filter_map(|(idx, weight)| {
-                let threshold = max_weight.checked_sub(exploration).unwrap_or(i16::MIN);
-                let within_threshold = *weight >= threshold;
-                if within_threshold {
-                    Some(*idx)
-                } else {
-                    None
-                }
-            })
-            .collect();
+        let key = if swapped { board.swap() } else { board };
+        let mut visits = [0; Board::COLUMN_LEN];
+        if let SelectionPolicy::Ucb { .. } = policy {
+            visits = self.visits.get(&key).copied().unwrap_or_default();
+            if swapped {
+                visits.reverse();
+            }
+        }
+
+        let column = policy.select(&available_choices, &visits, exploration, &mut self.rand);
 
-        let idx = self.rand.next() % available_choices.len();
-        let column = available_choices.swap_remove(idx);
+        if let SelectionPolicy::Ucb { .. } = policy {
+            let visits = self.visits.entry(key).or_default();
+            if swapped {
+                visits[Board::COLUMN_LEN - 1 - column] += 1;
+            } else {
+                visits[column] += 1;
+            }
+        }
 
         Choice { board, column }
     }
@@ -642,6 +985,13 @@ fn copy_from_to<const SRC_LEN: usize, const DEST_LEN: usize>(
     }
 }
 
+/// the `entry_len` long entries counted by the little endian u32 `bytes`
+/// start with, and whatever comes after them. `None` if they are cut short
+fn take_entries(bytes: &[u8], entry_len: usize) -> Option<(&[u8], &[u8])> {
+    let (len, rest) = bytes.split_first_chunk::<4>()?;
+    rest.split_at_checked(u32::from_le_bytes(*len) as usize * entry_len)
+}
+
 fn serialize_weights(
     board: &Board,
     weight: &Weight,
@@ -713,9 +1063,19 @@ fn deserialize_weights(
 
 #[cfg(test)]
 mod test {
-    use crate::board::Board;
+    use crate::board::{Board, Chip};
+
+    use crate::{
+        json::{FromJson, ToJson},
+        player::Player,
+        policy::{Schedule, SelectionPolicy},
+        reward::RewardConfig,
+    };
 
-    use super::{deserialize_weights, serialize_weights, Weight};
+    use super::{
+        deserialize_weights, evaluate, serialize_weights, Bot, BotTrainerBoardPosition,
+        GameStatistics, Trainer, Weight,
+    };
 
     #[test]
     fn serde() {
@@ -727,4 +1087,128 @@ mod test {
 
         assert_eq!((board, weights), result);
     }
+
+    #[test]
+    fn save_load() {
+        let mut board = Board::new();
+        let _ = board.place_chip(1, Chip::Red).unwrap();
+        let mut bot = Bot::new(0, 0);
+        bot.memory
+            .insert(board, Weight::from_weights([1, 2, 3, 4, 5, 6, 7]));
+
+        let path = std::env::temp_dir().join("connect-4-ai-save-load.bot");
+        bot.save(&path).unwrap();
+        let loaded = Bot::load(&path, 0, 0).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.weights(board), Some([1, 2, 3, 4, 5, 6, 7]));
+        assert_eq!(loaded.weights(board.swap()), Some([7, 6, 5, 4, 3, 2, 1]));
+        assert_eq!(loaded.weights(Board::new()), None);
+    }
+
+    #[test]
+    fn neutral_results_leave_the_last_move() {
+        let board = Board::new();
+        let mut bot = Bot::new(0, 0);
+        let choice = bot.choose(board);
+        let column = choice.column;
+        bot.remember_played_choice(choice);
+        let rewards =
+            RewardConfig::from_json_str(r#"{"yellow": {"tie": 0}, "severity": {"scale": 0}}"#)
+                .unwrap();
+
+        bot.learn_from_played_choices(rewards.action(Chip::Yellow, None), &rewards);
+        assert_eq!(bot.weights(board).unwrap()[column], 0);
+        bot.learn_from_board(Chip::Yellow, None, &rewards);
+        assert_eq!(bot.weights(board).unwrap()[column], 0);
+        bot.learn_from_board(Chip::Yellow, Some(Chip::Red), &rewards);
+        assert_eq!(bot.weights(board).unwrap()[column], i16::MIN);
+    }
+
+    #[test]
+    fn hint_leaves_the_bot_alone() {
+        let board = Board::from_moves("3").unwrap();
+        let mut bot = Bot::new(0, 0).with_policy(SelectionPolicy::Ucb { c: 0.0 });
+        bot.set_weights(board, [0, 0, 9, 0, 0, 0, 0]);
+        let rand = bot.rand.clone();
+
+        assert_eq!(bot.hint(board, Chip::Yellow), (2, Some(9)));
+        assert_eq!(bot.hint(Board::new(), Chip::Red), (0, Some(0)));
+        assert_eq!(bot.memory_len(), 1);
+        assert_eq!(bot.visits(board), None);
+        assert_eq!(bot.rand, rand);
+    }
+
+    #[test]
+    fn save_load_visits() {
+        let path = std::env::temp_dir().join("connect-4-ai-save-load-visits.bot");
+        let mut bot = Bot::new(0, 0).with_policy(SelectionPolicy::Ucb { c: 0.0 });
+        for _ in 0..Board::COLUMN_LEN + 2 {
+            bot.choose(Board::new());
+            bot.clear_played_choices();
+        }
+        bot.save(&path).unwrap();
+        let loaded = Bot::load(&path, 0, 0).unwrap();
+        assert_eq!(loaded.memory_len(), 1);
+        assert_eq!(loaded.visits(Board::new()), bot.visits(Board::new()));
+
+        // files of only weights are how bots used to be saved
+        let board = Board::from_moves("3").unwrap();
+        let weights = Weight::from_weights([1, 2, 3, 4, 5, 6, 7]);
+        std::fs::write(&path, serialize_weights(&board, &weights)).unwrap();
+        let loaded = Bot::load(&path, 0, 0).unwrap();
+        assert_eq!(loaded.weights(board), Some([1, 2, 3, 4, 5, 6, 7]));
+        assert_eq!(loaded.visits(board), None);
+
+        std::fs::write(&path, b"C4BT\x02\x00\x00\x00").unwrap();
+        assert!(Bot::load(&path, 0, 0).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn statistics_json() {
+        let statistics = GameStatistics {
+            red_wins: 3,
+            yellow_wins: 1,
+            ties: 2,
+        };
+        let json = statistics.to_json().to_string();
+        assert_eq!(json, r#"{"red_wins":3,"yellow_wins":1,"ties":2}"#);
+        assert_eq!(GameStatistics::from_json_str(&json).unwrap(), statistics);
+        assert!(GameStatistics::from_json_str(r#"{"red_wins":3}"#).is_err());
+    }
+
+    #[test]
+    fn trainer_schedule() {
+        let mut red = Bot::new(50, 1);
+        let mut yellow = Bot::new(50, 2);
+        let schedule = Schedule::Linear {
+            start: 50.0,
+            end: 0.0,
+            iterations: 10,
+        };
+        BotTrainerBoardPosition::new(&mut red, &mut yellow)
+            .with_schedule(schedule, 5)
+            .start_with_iterations(5);
+        // the last game was iteration 9
+        assert_eq!(red.exploration, 5);
+        assert_eq!(yellow.exploration, 5);
+    }
+
+    #[test]
+    fn greedy_evaluation() {
+        let mut bot =
+            Bot::new(i16::MAX, 1).with_policy(SelectionPolicy::EpsilonGreedy { epsilon: 1.0 });
+        bot.memory
+            .insert(Board::new(), Weight::from_weights([0, 0, 0, 9, 0, 0, 0]));
+        let mut opponent = Bot::new(i16::MAX, 2);
+        bot.set_greedy(true);
+        assert!((0..100).all(|_| bot.choose(Board::new()).column == 3));
+        bot.set_greedy(false);
+        assert!((0..100).any(|_| bot.choose(Board::new()).column != 3));
+
+        let score = evaluate(&mut bot, &mut opponent, 10);
+        assert_eq!(score.ties + score.first_wins + score.second_wins, 10);
+        assert!(!bot.greedy && !opponent.greedy);
+    }
 }
@@ -0,0 +1,224 @@
+#![allow(dead_code)]
+use std::io;
+
+use crate::{
+    board::{Board, Chip, Threat},
+    bot::Bot,
+    neural::Network,
+};
+
+/// scores the positions a minmax search ends on
+pub trait Evaluator {
+    /// how good `board` is for `maximizer`, strictly within ±1000, which are
+    /// won and lost games
+    fn evaluate(&self, board: &Board, maximizer: Chip) -> i16;
+}
+
+/// the original heuristic, `value_of_board` scaled up
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Opportunities;
+
+impl Evaluator for Opportunities {
+    fn evaluate(&self, board: &Board, maximizer: Chip) -> i16 {
+        board.value_of_board(maximizer) * 8
+    }
+}
+
+/// every line of four cells on the board, as `(column, row)`
+fn windows() -> impl Iterator<Item = [(usize, usize); 4]> {
+    let directions: [(isize, isize); 4] = [(1, -1), (1, 0), (0, 1), (1, 1)];
+    directions.into_iter().flat_map(|(column_dir, row_dir)| {
+        (0..Board::COLUMN_LEN).flat_map(move |column| {
+            (0..Board::ROW_LEN).filter_map(move |row| {
+                let cells: [(isize, isize); 4] = std::array::from_fn(|idx| {
+                    (
+                        column as isize + column_dir * idx as isize,
+                        row as isize + row_dir * idx as isize,
+                    )
+                });
+                cells
+                    .iter()
+                    .all(|&(column, row)| {
+                        (0..Board::COLUMN_LEN as isize).contains(&column)
+                            && (0..Board::ROW_LEN as isize).contains(&row)
+                    })
+                    .then(|| cells.map(|(column, row)| (column as usize, row as usize)))
+            })
+        })
+    })
+}
+
+/// lines with two chips of `chip` and two empty cells
+fn open_twos(board: &Board, chip: Chip) -> i32 {
+    windows()
+        .filter(|window| {
+            let chips = window.map(|(column, row)| board.chip_at(column, row));
+            chips
+                .iter()
+                .all(|other| other.is_none_or(|other| other == chip))
+                && chips.iter().filter(|other| other.is_some()).count() == 2
+        })
+        .count() as i32
+}
+
+/// the side to move winning on the next move decides the position, otherwise
+/// `score` of the maximizer's threats and open twos against the minimizer's
+fn score_threats(board: &Board, maximizer: Chip, score: impl Fn(&[Threat]) -> i32) -> i16 {
+    let turn = board.turn();
+    if !board.immediate_wins(turn).is_empty() {
+        return if turn == maximizer { 900 } else { -900 };
+    }
+    let side = |chip| score(&board.threats(chip)) + open_twos(board, chip);
+    let value = side(maximizer) - side(maximizer.opposite());
+    (value * 8).clamp(-899, 899) as i16
+}
+
+/// unfinished lines: cells that would connect four weigh a lot more than two
+/// chips with room for two more
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Threats;
+
+impl Evaluator for Threats {
+    fn evaluate(&self, board: &Board, maximizer: Chip) -> i16 {
+        score_threats(board, maximizer, |threats| threats.len() as i32 * 4)
+    }
+}
+
+/// like `Threats`, but knows that when the board fills up, red gets the odd
+/// rows and yellow the even ones, counting from 1 at the bottom. threats on
+/// a side's own rows are the ones it can cash in
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OddEven;
+
+impl Evaluator for OddEven {
+    fn evaluate(&self, board: &Board, maximizer: Chip) -> i16 {
+        score_threats(board, maximizer, |threats| {
+            threats
+                .iter()
+                .map(|threat| if threat.is_on_own_row() { 8 } else { 2 })
+                .sum()
+        })
+    }
+}
+
+/// the value head of a network
+impl Evaluator for Network {
+    fn evaluate(&self, board: &Board, maximizer: Chip) -> i16 {
+        let value = (Network::evaluate(self, board).value * 900.0) as i16;
+        if board.turn() == maximizer {
+            value
+        } else {
+            -value
+        }
+    }
+}
+
+/// the best weight a bot has for the side to move, or the opportunities for
+/// boards the bot has never seen
+impl Evaluator for Bot {
+    fn evaluate(&self, board: &Board, maximizer: Chip) -> i16 {
+        let Some(weights) = self.weights(*board) else {
+            return Opportunities.evaluate(board, maximizer);
+        };
+        let available = board.available_column_choices();
+        let best = (0..Board::COLUMN_LEN)
+            .filter(|&column| available[column])
+            .map(|column| weights[column])
+            .max()
+            .unwrap_or(0)
+            .clamp(-999, 999);
+        if board.turn() == maximizer {
+            best
+        } else {
+            -best
+        }
+    }
+}
+
+/// `opportunities`, `threats`, `oddeven`, `net:<path>` or `bot:<path>`
+pub fn evaluator_from_spec(spec: &str) -> io::Result<Box<dyn Evaluator>> {
+    match spec.split_once(':') {
+        None if spec == "opportunities" => Ok(Box::new(Opportunities)),
+        None if spec == "threats" => Ok(Box::new(Threats)),
+        None if spec == "oddeven" => Ok(Box::new(OddEven)),
+        Some(("net", path)) => Ok(Box::new(Network::load(path)?)),
+        Some(("bot", path)) => Ok(Box::new(Bot::load(path, 0, 0)?)),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unknown evaluator '{spec}'"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::board::{Board, Chip};
+
+    use super::{evaluator_from_spec, windows, Evaluator, OddEven, Opportunities, Threats};
+
+    #[test]
+    fn every_window() {
+        // 24 horizontal, 21 vertical and 12 in each diagonal
+        assert_eq!(windows().count(), 69);
+    }
+
+    #[test]
+    fn opportunities_is_the_old_heuristic() {
+        let board = Board::from_moves("3342").unwrap();
+        assert_eq!(
+            Opportunities.evaluate(&board, Chip::Red),
+            board.value_of_board(Chip::Red) * 8
+        );
+    }
+
+    /// chips stacked from the bottom of each column, regardless of turns
+    fn stacks(columns: &[&str]) -> Board {
+        let mut board = Board::new();
+        for (column, chips) in columns.iter().enumerate() {
+            for chip in chips.chars() {
+                let chip = if chip == 'r' { Chip::Red } else { Chip::Yellow };
+                board.place_chip(column, chip).unwrap();
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn threats() {
+        // red has 3 in a row on the bottom with both ends open, and it is
+        // red's turn
+        let board = Board::from_moves("203040").unwrap();
+        assert_eq!(Threats.evaluate(&board, Chip::Red), 900);
+        assert_eq!(Threats.evaluate(&board, Chip::Yellow), -900);
+
+        // the same line with yellow to move, red is still way ahead with its
+        // two threats
+        let board = Board::from_moves("20304").unwrap();
+        assert!(Threats.evaluate(&board, Chip::Red) >= 2 * 4 * 8);
+        assert_eq!(
+            Threats.evaluate(&board, Chip::Red),
+            -Threats.evaluate(&board, Chip::Yellow)
+        );
+    }
+
+    #[test]
+    fn odd_threats_are_reds() {
+        // a threat on the third row at column 3, which is red's to cash in
+        let red = stacks(&["yrr", "ryr", "yrr"]);
+        let yellow = stacks(&["ryy", "yry", "ryy"]);
+        assert_eq!(
+            Threats.evaluate(&red, Chip::Red),
+            Threats.evaluate(&yellow, Chip::Yellow)
+        );
+        assert!(OddEven.evaluate(&red, Chip::Red) > OddEven.evaluate(&yellow, Chip::Yellow));
+    }
+
+    #[test]
+    fn from_spec() {
+        for spec in ["opportunities", "threats", "oddeven"] {
+            assert!(evaluator_from_spec(spec).is_ok());
+        }
+        assert!(evaluator_from_spec("vibes").is_err());
+        assert!(evaluator_from_spec("net:/does/not/exist").is_err());
+    }
+}
@@ -0,0 +1,354 @@
+#![allow(dead_code)]
+use std::fmt::Display;
+
+use crate::{
+    arena::{alternate_sides, play_game},
+    bot::{Bot, BotTrainerGameResult, Trainer},
+    player::Player,
+    rand::{Rand, Rng},
+    reward::{RewardConfig, SeverityCurve, SideRewards},
+};
+
+/// the hyperparameters of a bot, everything evolution gets to change
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Genome {
+    pub exploration: i16,
+    pub win_reward: u32,
+    pub loss_punishment: u32,
+    /// red is punished and yellow rewarded this much for a tie
+    pub tie_reward: u32,
+    pub severity: SeverityCurve,
+}
+
+impl Genome {
+    const EXPLORATION: (i16, i16) = (0, 100);
+    const REWARD: (u32, u32) = (1, 50);
+    const TIE_REWARD: (u32, u32) = (0, 10);
+    const SEVERITY_SCALE: (f64, f64) = (0.0, 0.1);
+    const SEVERITY_EXPONENT: (f64, f64) = (1.0, 3.0);
+
+    pub fn random(rand: &mut impl Rng) -> Self {
+        let float =
+            |rand: &mut dyn Rng, (min, max): (f64, f64)| min + rand.next_f64() * (max - min);
+        Self {
+            exploration: rand.range(Self::EXPLORATION.0 as usize..Self::EXPLORATION.1 as usize + 1)
+                as i16,
+            win_reward: rand.range(Self::REWARD.0 as usize..Self::REWARD.1 as usize + 1) as u32,
+            loss_punishment: rand.range(Self::REWARD.0 as usize..Self::REWARD.1 as usize + 1)
+                as u32,
+            tie_reward: rand.range(Self::TIE_REWARD.0 as usize..Self::TIE_REWARD.1 as usize + 1)
+                as u32,
+            severity: SeverityCurve {
+                scale: float(rand, Self::SEVERITY_SCALE),
+                exponent: float(rand, Self::SEVERITY_EXPONENT),
+            },
+        }
+    }
+
+    /// nudges every gene with probability `rate`, by up to a tenth of its range
+    pub fn mutate(&mut self, rate: f64, rand: &mut impl Rng) {
+        fn nudge(value: f64, (min, max): (f64, f64), rand: &mut dyn Rng) -> f64 {
+            let step = (max - min) / 10.0;
+            (value + (rand.next_f64() * 2.0 - 1.0) * step).clamp(min, max)
+        }
+        let int = |(min, max): (i16, i16)| (min as f64, max as f64);
+        let uint = |(min, max): (u32, u32)| (min as f64, max as f64);
+
+        if rand.next_f64() < rate {
+            self.exploration =
+                nudge(self.exploration as f64, int(Self::EXPLORATION), rand).round() as i16;
+        }
+        if rand.next_f64() < rate {
+            self.win_reward =
+                nudge(self.win_reward as f64, uint(Self::REWARD), rand).round() as u32;
+        }
+        if rand.next_f64() < rate {
+            self.loss_punishment =
+                nudge(self.loss_punishment as f64, uint(Self::REWARD), rand).round() as u32;
+        }
+        if rand.next_f64() < rate {
+            self.tie_reward =
+                nudge(self.tie_reward as f64, uint(Self::TIE_REWARD), rand).round() as u32;
+        }
+        if rand.next_f64() < rate {
+            self.severity.scale = nudge(self.severity.scale, Self::SEVERITY_SCALE, rand);
+        }
+        if rand.next_f64() < rate {
+            self.severity.exponent = nudge(self.severity.exponent, Self::SEVERITY_EXPONENT, rand);
+        }
+    }
+
+    /// every gene from either parent, with even odds
+    pub fn crossover(&self, other: &Self, rand: &mut impl Rng) -> Self {
+        let mut pick = || rand.next_f64() < 0.5;
+        Self {
+            exploration: if pick() {
+                self.exploration
+            } else {
+                other.exploration
+            },
+            win_reward: if pick() {
+                self.win_reward
+            } else {
+                other.win_reward
+            },
+            loss_punishment: if pick() {
+                self.loss_punishment
+            } else {
+                other.loss_punishment
+            },
+            tie_reward: if pick() {
+                self.tie_reward
+            } else {
+                other.tie_reward
+            },
+            severity: SeverityCurve {
+                scale: if pick() {
+                    self.severity.scale
+                } else {
+                    other.severity.scale
+                },
+                exponent: if pick() {
+                    self.severity.exponent
+                } else {
+                    other.severity.exponent
+                },
+            },
+        }
+    }
+
+    pub fn bot(&self, rand: Rand) -> Bot {
+        Bot::with_rand(self.exploration, rand)
+    }
+
+    pub fn rewards(&self) -> RewardConfig {
+        let side = |tie: i32| SideRewards {
+            win: self.win_reward as i32,
+            loss: -(self.loss_punishment as i32),
+            tie,
+        };
+        RewardConfig {
+            red: side(-(self.tie_reward as i32)),
+            yellow: side(self.tie_reward as i32),
+            severity: self.severity,
+            ..RewardConfig::default()
+        }
+    }
+
+    /// self-play between two bots with this genome's rewards
+    pub fn train(&self, red: &mut Bot, yellow: &mut Bot, games: usize) {
+        BotTrainerGameResult::new(red, yellow)
+            .with_rewards(self.rewards())
+            .quiet()
+            .start_with_iterations(games);
+    }
+}
+
+impl Display for Genome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "exploration {}, win {}, loss {}, tie {}, severity {:.4} * turn^{:.2}",
+            self.exploration,
+            self.win_reward,
+            self.loss_punishment,
+            self.tie_reward,
+            self.severity.scale,
+            self.severity.exponent
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EvolutionConfig {
+    pub population: usize,
+    /// how many of the fittest genomes survive unchanged
+    pub elites: usize,
+    pub mutation_rate: f64,
+    /// how many genomes compete for each parent slot
+    pub tournament_size: usize,
+    pub training_games: usize,
+    /// games against each reference opponent
+    pub evaluation_games: usize,
+}
+
+impl Default for EvolutionConfig {
+    fn default() -> Self {
+        Self {
+            population: 16,
+            elites: 2,
+            mutation_rate: 0.2,
+            tournament_size: 3,
+            training_games: 10_000,
+            evaluation_games: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Individual {
+    pub genome: Genome,
+    /// points per evaluation game, a win is 1 and a tie 0.5
+    pub fitness: f64,
+}
+
+/// evolves bot hyperparameters. every generation each genome trains a bot
+/// from scratch, which then plays greedily against the reference opponents
+/// to measure the fitness of the genome
+pub struct EvolutionTrainer {
+    config: EvolutionConfig,
+    references: Vec<Box<dyn Player>>,
+    population: Vec<Genome>,
+    rand: Rand,
+    best: Option<(Individual, Bot)>,
+}
+
+impl EvolutionTrainer {
+    pub fn new(config: EvolutionConfig, references: Vec<Box<dyn Player>>, seed: usize) -> Self {
+        assert!(!references.is_empty(), "fitness needs reference opponents");
+        assert!(config.elites <= config.population);
+        let mut rand = Rand::new(seed);
+        let population = (0..config.population)
+            .map(|_| Genome::random(&mut rand))
+            .collect();
+        Self {
+            config,
+            references,
+            population,
+            rand,
+            best: None,
+        }
+    }
+
+    fn fitness(&mut self, bot: &mut Bot) -> f64 {
+        bot.set_greedy(true);
+        let mut points = 0.0;
+        for reference in &mut self.references {
+            let score = alternate_sides::<dyn Player>(
+                bot,
+                reference.as_mut(),
+                self.config.evaluation_games,
+                play_game,
+            );
+            points += score.first_wins as f64 + score.ties as f64 / 2.0;
+        }
+        bot.set_greedy(false);
+        points / (self.config.evaluation_games * self.references.len()) as f64
+    }
+
+    fn tournament<'individuals>(
+        &mut self,
+        individuals: &'individuals [Individual],
+    ) -> &'individuals Genome {
+        let contestants = (0..self.config.tournament_size.max(1))
+            .map(|_| &individuals[self.rand.below(individuals.len())]);
+        &contestants
+            .max_by(|left, right| left.fitness.total_cmp(&right.fitness))
+            .expect("at least one contestant")
+            .genome
+    }
+
+    /// trains and scores the current population, then breeds the next one.
+    /// returns the individuals of the generation, fittest first
+    pub fn generation(&mut self) -> Vec<Individual> {
+        let mut individuals = Vec::with_capacity(self.population.len());
+        for genome in std::mem::take(&mut self.population) {
+            let mut bot = genome.bot(self.rand.split());
+            let mut sparring = genome.bot(self.rand.split());
+            genome.train(&mut bot, &mut sparring, self.config.training_games);
+            let fitness = self.fitness(&mut bot);
+            let individual = Individual { genome, fitness };
+            if self
+                .best
+                .as_ref()
+                .is_none_or(|(best, _)| fitness > best.fitness)
+            {
+                self.best = Some((individual.clone(), bot));
+            }
+            individuals.push(individual);
+        }
+        individuals.sort_by(|left, right| right.fitness.total_cmp(&left.fitness));
+
+        let mut next: Vec<_> = individuals
+            .iter()
+            .take(self.config.elites)
+            .map(|individual| individual.genome)
+            .collect();
+        while next.len() < self.config.population {
+            let mother = *self.tournament(&individuals);
+            let father = *self.tournament(&individuals);
+            let mut child = mother.crossover(&father, &mut self.rand);
+            child.mutate(self.config.mutation_rate, &mut self.rand);
+            next.push(child);
+        }
+        self.population = next;
+        individuals
+    }
+
+    /// runs `generations` generations, returning the fittest individual seen
+    /// and the bot it trained
+    pub fn evolve(mut self, generations: usize) -> (Individual, Bot) {
+        for generation in 0..generations {
+            let individuals = self.generation();
+            let fittest = &individuals[0];
+            println!(
+                "generation {generation}: best fitness {:.3} ({})",
+                fittest.fitness, fittest.genome
+            );
+        }
+        self.best
+            .expect("at least one generation with at least one genome")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        player::{MinmaxPlayer, Player},
+        rand::Rand,
+    };
+
+    use super::{EvolutionConfig, EvolutionTrainer, Genome};
+
+    #[test]
+    fn genes_stay_in_range() {
+        let mut rand = Rand::new(3);
+        let mut genome = Genome::random(&mut rand);
+        for _ in 0..1000 {
+            let other = Genome::random(&mut rand);
+            genome = genome.crossover(&other, &mut rand);
+            genome.mutate(1.0, &mut rand);
+            assert!((0..=100).contains(&genome.exploration));
+            assert!((1..=50).contains(&genome.win_reward));
+            assert!((1..=50).contains(&genome.loss_punishment));
+            assert!(genome.tie_reward <= 10);
+            assert!((0.0..=0.1).contains(&genome.severity.scale));
+            assert!((1.0..=3.0).contains(&genome.severity.exponent));
+        }
+    }
+
+    #[test]
+    fn elites_survive() {
+        let config = EvolutionConfig {
+            population: 4,
+            elites: 2,
+            training_games: 20,
+            evaluation_games: 4,
+            ..EvolutionConfig::default()
+        };
+        let references: Vec<Box<dyn Player>> = vec![Box::new(MinmaxPlayer::new(0))];
+        let mut trainer = EvolutionTrainer::new(config, references, 7);
+        let individuals = trainer.generation();
+        assert_eq!(individuals.len(), 4);
+        assert!(individuals[0].fitness >= individuals[3].fitness);
+        assert_eq!(trainer.population.len(), 4);
+        assert_eq!(
+            trainer.population[..2],
+            [individuals[0].genome, individuals[1].genome]
+        );
+
+        let (best, bot) = trainer.evolve(1);
+        assert!(best.fitness >= individuals[0].fitness);
+        assert!(bot.memory_len() > 0);
+    }
+}
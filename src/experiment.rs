@@ -0,0 +1,560 @@
+#![allow(dead_code)]
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{
+    arena::{play_game, play_match},
+    bot::{
+        evaluate_match, Bot, BotTrainerBoardPosition, BotTrainerGameResult, GladiatorBotTrainer,
+        MinMaxBotTrainer, Trainer,
+    },
+    json::{FromJson, Json, JsonError, ToJson},
+    player::{player_from_spec, Player},
+    policy::{Schedule, SelectionPolicy},
+    reward::RewardConfig,
+    stats::{MatchStats, Sprt},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrainerKind {
+    GameResult,
+    BoardPosition,
+    MinMax,
+    /// `iterations` are the games of every fight
+    Gladiator {
+        arena_size: usize,
+    },
+}
+
+/// one of the bots being trained
+#[derive(Debug, Clone, PartialEq)]
+pub struct BotConfig {
+    pub exploration: i16,
+    pub seed: usize,
+    pub policy: SelectionPolicy,
+    /// continue training a saved bot instead of starting from scratch
+    pub load: Option<String>,
+}
+
+impl BotConfig {
+    pub fn bot(&self) -> io::Result<Bot> {
+        let bot = match &self.load {
+            Some(path) => Bot::load(path, self.exploration, self.seed)?,
+            None => Bot::new(self.exploration, self.seed),
+        };
+        Ok(bot.with_policy(self.policy))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Opponent {
+    /// the trained bot as it was at the last evaluation
+    Previous,
+    /// anything `player_from_spec` understands, i.e. `minmax:3`
+    Engine(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvaluationConfig {
+    /// iterations between evaluations, or only once at the end
+    pub every: Option<usize>,
+    pub games: usize,
+    pub against: Opponent,
+    /// stop evaluating before `games` once the test has a verdict
+    pub sprt: Option<Sprt>,
+}
+
+/// everything a training run needs, so it can be repeated from a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Experiment {
+    pub name: String,
+    pub trainer: TrainerKind,
+    pub red: BotConfig,
+    /// the sparring partner of the self-play trainers, unused otherwise
+    pub yellow: BotConfig,
+    pub iterations: usize,
+    pub schedule: Option<Schedule>,
+    pub rewards: RewardConfig,
+    pub evaluation: Option<EvaluationConfig>,
+    /// where the trained bot is saved
+    pub bot_path: Option<String>,
+    /// where evaluations are written, one json object per line
+    pub log_path: Option<String>,
+}
+
+/// one evaluation of the trained bot, from its point of view
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Checkpoint {
+    pub iteration: usize,
+    pub stats: MatchStats,
+}
+
+impl ToJson for Checkpoint {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("iteration", Json::from(self.iteration)),
+            ("stats", self.stats.to_json()),
+        ])
+    }
+}
+
+impl Experiment {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::from_json_str(&text)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+    }
+
+    /// settings the trainer can't honour
+    fn check(&self) -> io::Result<()> {
+        let unsupported = |setting: &str| {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("the gladiator trainer does not support '{setting}'"),
+            ))
+        };
+        if let TrainerKind::Gladiator { arena_size } = self.trainer {
+            if arena_size == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "a gladiator arena needs at least one fight",
+                ));
+            }
+            if self.red.load.is_some() {
+                // the gladiators are all new bots
+                return unsupported("load");
+            }
+            if self
+                .evaluation
+                .as_ref()
+                .is_some_and(|evaluation| evaluation.every.is_some())
+            {
+                // the winner is only known at the end
+                return unsupported("every");
+            }
+        }
+        Ok(())
+    }
+
+    /// trains a bot as configured, evaluating it along the way. checkpoints
+    /// are written to `log` as json lines
+    pub fn run(&self, log: &mut impl Write) -> io::Result<(Bot, Vec<Checkpoint>)> {
+        self.check()?;
+        let mut checkpoints = Vec::new();
+        let mut evaluation = match &self.evaluation {
+            Some(evaluation) => Some((evaluation, self.opponent(evaluation)?)),
+            None => None,
+        };
+        let mut checkpoint = |bot: &mut Bot, iteration: usize| -> io::Result<()> {
+            let Some((config, opponent)) = &mut evaluation else {
+                return Ok(());
+            };
+            let stats = match opponent {
+                OpponentPlayer::Previous(previous) => {
+                    let stats = evaluate_match(bot, previous, config.games, config.sprt);
+                    **previous = bot.clone();
+                    stats
+                }
+                OpponentPlayer::Engine(engine) => {
+                    bot.set_greedy(true);
+                    let stats = play_match::<dyn Player>(
+                        bot,
+                        engine.as_mut(),
+                        config.games,
+                        config.sprt,
+                        play_game,
+                    );
+                    bot.set_greedy(false);
+                    stats
+                }
+            };
+            let checkpoint = Checkpoint { iteration, stats };
+            writeln!(log, "{}", checkpoint.to_json())?;
+            checkpoints.push(checkpoint);
+            Ok(())
+        };
+
+        let bot = if let TrainerKind::Gladiator { arena_size } = self.trainer {
+            let trainer = GladiatorBotTrainer::seeded(arena_size, self.red.seed)
+                .with_exploration(self.red.exploration);
+            let mut bot = self
+                .configure(trainer, 0)
+                .the_one_bot_to_rule_them_all(self.iterations);
+            bot.policy = self.red.policy;
+            checkpoint(&mut bot, self.iterations)?;
+            bot
+        } else {
+            let mut bot = self.red.bot()?;
+            let mut sparring = self.yellow.bot()?;
+            let every = self
+                .evaluation
+                .as_ref()
+                .and_then(|evaluation| evaluation.every)
+                .unwrap_or(self.iterations)
+                .max(1);
+            let mut done = 0;
+            while done < self.iterations {
+                let iterations = every.min(self.iterations - done);
+                self.train(&mut bot, &mut sparring, done, iterations);
+                done += iterations;
+                checkpoint(&mut bot, done)?;
+            }
+            bot
+        };
+
+        if let Some(path) = &self.bot_path {
+            bot.save(path)?;
+        }
+        Ok((bot, checkpoints))
+    }
+
+    fn opponent(&self, evaluation: &EvaluationConfig) -> io::Result<OpponentPlayer> {
+        match &evaluation.against {
+            Opponent::Previous => Ok(OpponentPlayer::Previous(Box::new(self.red.bot()?))),
+            Opponent::Engine(spec) => Ok(OpponentPlayer::Engine(player_from_spec(spec)?)),
+        }
+    }
+
+    /// the rewards and schedule of the experiment. trainers are quiet, so
+    /// their progress doesn't end up between the checkpoints of the log
+    fn configure<T: Trainer>(&self, trainer: T, first_iteration: usize) -> T {
+        let trainer = trainer.with_rewards(self.rewards).quiet();
+        match self.schedule {
+            Some(schedule) => trainer.with_schedule(schedule, first_iteration),
+            None => trainer,
+        }
+    }
+
+    fn train(&self, bot: &mut Bot, sparring: &mut Bot, first_iteration: usize, iterations: usize) {
+        match self.trainer {
+            TrainerKind::GameResult => self
+                .configure(BotTrainerGameResult::new(bot, sparring), first_iteration)
+                .start_with_iterations(iterations),
+            TrainerKind::BoardPosition => self
+                .configure(BotTrainerBoardPosition::new(bot, sparring), first_iteration)
+                .start_with_iterations(iterations),
+            TrainerKind::MinMax => {
+                self.configure(MinMaxBotTrainer::new(bot), first_iteration)
+                    .start_with_iterations(iterations);
+                return;
+            }
+            TrainerKind::Gladiator { .. } => unreachable!("gladiators are trained in one go"),
+        }
+        // the two bot trainers swap their bots after every game, so after an
+        // odd number of games `bot` would be the sparring bot
+        if iterations % 2 == 1 {
+            std::mem::swap(bot, sparring);
+        }
+    }
+}
+
+enum OpponentPlayer {
+    Previous(Box<Bot>),
+    Engine(Box<dyn Player>),
+}
+
+impl ToJson for TrainerKind {
+    fn to_json(&self) -> Json {
+        match self {
+            TrainerKind::GameResult => Json::from("game_result"),
+            TrainerKind::BoardPosition => Json::from("board_position"),
+            TrainerKind::MinMax => Json::from("minmax"),
+            TrainerKind::Gladiator { arena_size } => Json::object([
+                ("kind", Json::from("gladiator")),
+                ("arena_size", Json::from(*arena_size)),
+            ]),
+        }
+    }
+}
+
+/// a name, or an object with a `kind` for trainers with settings
+impl FromJson for TrainerKind {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        let kind = match json.as_str() {
+            Some(kind) => kind,
+            None => json.field_as("kind", Json::as_str)?,
+        };
+        match kind {
+            "game_result" => Ok(TrainerKind::GameResult),
+            "board_position" => Ok(TrainerKind::BoardPosition),
+            "minmax" => Ok(TrainerKind::MinMax),
+            "gladiator" => Ok(TrainerKind::Gladiator {
+                arena_size: json.field_or("arena_size", 16, Json::as_usize)?,
+            }),
+            kind => Err(JsonError::InvalidValue(format!("unknown trainer '{kind}'"))),
+        }
+    }
+}
+
+impl ToJson for BotConfig {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("exploration", Json::from(self.exploration)),
+            ("seed", Json::from(self.seed)),
+            ("policy", self.policy.to_json()),
+            ("load", Json::from(self.load.clone())),
+        ])
+    }
+}
+
+impl FromJson for BotConfig {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        Ok(Self {
+            exploration: json.field_or("exploration", 50, |json| json.as_i64()?.try_into().ok())?,
+            seed: json.field_as("seed", Json::as_usize)?,
+            policy: match json.get("policy") {
+                Some(policy) => SelectionPolicy::from_json(policy)?,
+                None => SelectionPolicy::default(),
+            },
+            load: match json.get("load") {
+                None | Some(Json::Null) => None,
+                Some(_) => Some(json.field_as("load", Json::as_str)?.to_string()),
+            },
+        })
+    }
+}
+
+impl ToJson for EvaluationConfig {
+    fn to_json(&self) -> Json {
+        let against = match &self.against {
+            Opponent::Previous => "previous",
+            Opponent::Engine(spec) => spec,
+        };
+        Json::object([
+            ("every", Json::from(self.every)),
+            ("games", Json::from(self.games)),
+            ("against", Json::from(against)),
+            (
+                "sprt",
+                self.sprt.as_ref().map_or(Json::Null, ToJson::to_json),
+            ),
+        ])
+    }
+}
+
+/// `against` is `previous` or an engine, and `sprt` is optional
+impl FromJson for EvaluationConfig {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        Ok(Self {
+            every: match json.get("every") {
+                None | Some(Json::Null) => None,
+                Some(_) => Some(json.field_as("every", Json::as_usize)?),
+            },
+            games: json.field_as("games", Json::as_usize)?,
+            against: match json.field_or("against", "previous", Json::as_str)? {
+                "previous" => Opponent::Previous,
+                spec => Opponent::Engine(spec.to_string()),
+            },
+            sprt: match json.get("sprt") {
+                None | Some(Json::Null) => None,
+                Some(sprt) => Some(Sprt::from_json(sprt)?),
+            },
+        })
+    }
+}
+
+impl ToJson for Experiment {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("name", Json::from(self.name.as_str())),
+            ("trainer", self.trainer.to_json()),
+            ("red", self.red.to_json()),
+            ("yellow", self.yellow.to_json()),
+            ("iterations", Json::from(self.iterations)),
+            (
+                "schedule",
+                self.schedule.as_ref().map_or(Json::Null, ToJson::to_json),
+            ),
+            ("rewards", self.rewards.to_json()),
+            (
+                "evaluation",
+                self.evaluation.as_ref().map_or(Json::Null, ToJson::to_json),
+            ),
+            ("bot", Json::from(self.bot_path.clone())),
+            ("log", Json::from(self.log_path.clone())),
+        ])
+    }
+}
+
+/// `schedule`, `rewards`, `evaluation`, `bot` and `log` are optional, and
+/// `rewards` can also be the path of a separate rewards file
+impl FromJson for Experiment {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        let optional = |key: &str| json.get(key).filter(|value| **value != Json::Null);
+        let path = |key: &str| -> Result<Option<String>, JsonError> {
+            optional(key)
+                .map(|_| json.field_as(key, Json::as_str).map(str::to_string))
+                .transpose()
+        };
+        let rewards = match optional("rewards") {
+            None => RewardConfig::default(),
+            Some(Json::String(path)) => RewardConfig::load(path)
+                .map_err(|err| JsonError::InvalidValue(format!("rewards '{path}': {err}")))?,
+            Some(rewards) => RewardConfig::from_json(rewards)?,
+        };
+        Ok(Self {
+            name: json
+                .field_or("name", "experiment", Json::as_str)?
+                .to_string(),
+            trainer: TrainerKind::from_json(json.field("trainer")?)?,
+            red: BotConfig::from_json(json.field("red")?)?,
+            yellow: match optional("yellow") {
+                Some(yellow) => BotConfig::from_json(yellow)?,
+                None => BotConfig::from_json(json.field("red")?).map(|red| BotConfig {
+                    seed: red.seed.wrapping_add(1),
+                    load: None,
+                    ..red
+                })?,
+            },
+            iterations: json.field_as("iterations", Json::as_usize)?,
+            schedule: optional("schedule").map(Schedule::from_json).transpose()?,
+            rewards,
+            evaluation: optional("evaluation")
+                .map(EvaluationConfig::from_json)
+                .transpose()?,
+            bot_path: path("bot")?,
+            log_path: path("log")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        json::{FromJson, Json, ToJson},
+        policy::{Schedule, SelectionPolicy},
+        stats::Sprt,
+    };
+
+    use super::{Experiment, Opponent, TrainerKind};
+
+    const CONFIG: &str = r#"{
+        "name": "tiny",
+        "trainer": "board_position",
+        "red": {"exploration": 20, "seed": 7, "policy": "softmax:5"},
+        "iterations": 40,
+        "schedule": {"kind": "linear", "start": 5, "end": 0, "iterations": 40},
+        "rewards": {"yellow": {"tie": 0}},
+        "evaluation": {"every": 20, "games": 4, "against": "minmax:0", "sprt": {"elo": 100}}
+    }"#;
+
+    #[test]
+    fn json() {
+        let experiment = Experiment::from_json_str(CONFIG).unwrap();
+        assert_eq!(experiment.trainer, TrainerKind::BoardPosition);
+        assert_eq!(
+            experiment.red.policy,
+            SelectionPolicy::Softmax { temperature: 5.0 }
+        );
+        assert_eq!(experiment.yellow.seed, 8);
+        assert_eq!(experiment.yellow.exploration, 20);
+        assert_eq!(experiment.rewards.yellow.tie, 0);
+        assert!(matches!(
+            experiment.schedule,
+            Some(Schedule::Linear { iterations: 40, .. })
+        ));
+        assert_eq!(
+            experiment.evaluation.as_ref().unwrap().against,
+            Opponent::Engine("minmax:0".to_string())
+        );
+        assert_eq!(
+            experiment.evaluation.as_ref().unwrap().sprt,
+            Some(Sprt {
+                elo: 100.0,
+                ..Sprt::default()
+            })
+        );
+        assert_eq!(experiment.bot_path, None);
+        assert_eq!(
+            Experiment::from_json(&experiment.to_json()).unwrap(),
+            experiment
+        );
+        assert!(Experiment::from_json_str(r#"{"trainer": "minmax"}"#).is_err());
+    }
+
+    #[test]
+    fn runs_are_reproducible() {
+        let experiment = Experiment::from_json_str(CONFIG).unwrap();
+        let mut log = Vec::new();
+        let (bot, checkpoints) = experiment.run(&mut log).unwrap();
+        assert_eq!(
+            checkpoints
+                .iter()
+                .map(|checkpoint| checkpoint.iteration)
+                .collect::<Vec<_>>(),
+            [20, 40]
+        );
+        let log = String::from_utf8(log).unwrap();
+        assert_eq!(log.lines().count(), 2);
+        let first = Json::parse(log.lines().next().unwrap()).unwrap();
+        let stats = first.field("stats").unwrap();
+        assert_eq!(
+            stats.get("as_red"),
+            Some(&checkpoints[0].stats.as_red.to_json())
+        );
+        assert_eq!(
+            checkpoints[0].stats.total().games(),
+            4,
+            "no verdict after 4 games"
+        );
+
+        let (again, again_checkpoints) = experiment.run(&mut Vec::new()).unwrap();
+        assert_eq!(checkpoints, again_checkpoints);
+        assert_eq!(bot.memory_len(), again.memory_len());
+    }
+
+    #[test]
+    fn samples() {
+        for entry in std::fs::read_dir("experiments").unwrap() {
+            let path = entry.unwrap().path();
+            let experiment = Experiment::load(&path).unwrap();
+            assert!(experiment.check().is_ok(), "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn gladiator_rejects_what_it_ignores() {
+        let gladiator = |extra: &str| {
+            Experiment::from_json_str(&format!(
+                r#"{{"trainer": {{"kind": "gladiator", "arena_size": 2}},
+                    "red": {{"exploration": 7, "seed": 3{extra}}}, "iterations": 2}}"#
+            ))
+            .unwrap()
+        };
+        let (bot, checkpoints) = gladiator("").run(&mut Vec::new()).unwrap();
+        assert_eq!(bot.exploration, 7);
+        assert_eq!(checkpoints.len(), 0);
+
+        let load = gladiator(r#", "load": "red.bot""#);
+        assert!(load.run(&mut Vec::new()).is_err());
+        let mut every = gladiator("");
+        every.evaluation = Some(super::EvaluationConfig {
+            every: Some(1),
+            games: 1,
+            against: Opponent::Previous,
+            sprt: None,
+        });
+        assert!(every.run(&mut Vec::new()).is_err());
+
+        let mut empty = gladiator("");
+        empty.trainer = TrainerKind::Gladiator { arena_size: 0 };
+        assert!(empty.run(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn odd_chunks_keep_the_red_bot() {
+        let experiment = Experiment::from_json_str(
+            r#"{"trainer": "game_result", "iterations": 3,
+                "red": {"exploration": 20, "seed": 1},
+                "yellow": {"exploration": 3, "seed": 2},
+                "evaluation": {"every": 1, "games": 1, "against": "minmax:0"}}"#,
+        )
+        .unwrap();
+        let (bot, checkpoints) = experiment.run(&mut Vec::new()).unwrap();
+        assert_eq!(checkpoints.len(), 3);
+        assert_eq!(bot.exploration, 20);
+    }
+}
@@ -0,0 +1,357 @@
+#![allow(dead_code)]
+use std::{cmp::Ordering, fs, io, path::Path};
+
+use crate::{
+    board::{Board, Chip, SearchInfo},
+    bot::Bot,
+    player::{Player, SearchLimits},
+};
+
+/// what an exported policy keeps for each board
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PolicyKind {
+    /// only the column the bot liked best
+    BestColumn,
+    /// all 7 weights
+    Weights,
+}
+
+impl PolicyKind {
+    const fn tag(self) -> u8 {
+        match self {
+            PolicyKind::BestColumn => 0,
+            PolicyKind::Weights => 1,
+        }
+    }
+
+    const fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(PolicyKind::BestColumn),
+            1 => Some(PolicyKind::Weights),
+            _ => None,
+        }
+    }
+
+    /// the bytes after the board in every entry
+    const fn value_len(self) -> usize {
+        match self {
+            PolicyKind::BestColumn => std::mem::size_of::<u8>(),
+            PolicyKind::Weights => std::mem::size_of::<i16>() * Board::COLUMN_LEN,
+        }
+    }
+}
+
+/// what a policy has for a board
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PolicyMove {
+    Column(usize),
+    Weights([i16; Board::COLUMN_LEN]),
+}
+
+/// a bot's memory without any of its training state, ready to be played
+/// with. the file is searched as it is, so loading it is a single read, and
+/// could just as well be memory mapped
+///
+/// `C4PL`, the kind, 3 bytes of padding and the number of entries as a u32,
+/// then for every canonical board in ascending order its two halves and
+/// either the best column as a u8 or the 7 weights as i16s, all little endian
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactPolicy {
+    kind: PolicyKind,
+    bytes: Vec<u8>,
+}
+
+impl CompactPolicy {
+    const MAGIC: &'static [u8; 4] = b"C4PL";
+    const HEADER_LEN: usize = 12;
+    const KEY_LEN: usize = std::mem::size_of::<u64>() + std::mem::size_of::<u32>();
+
+    /// every board `bot` remembers that still has a move left
+    pub fn from_bot(bot: &Bot, kind: PolicyKind) -> Self {
+        let mut entries: Vec<_> = bot
+            .memory()
+            .filter(|(board, _)| !board.filled())
+            .map(|(board, mut weights)| {
+                let (key, swapped) = board.canonical();
+                if swapped {
+                    weights.reverse();
+                }
+                (key, weights)
+            })
+            .collect();
+        entries.sort_by_key(|(board, _)| board.as_pair());
+        entries.dedup_by_key(|(board, _)| board.as_pair());
+
+        let mut bytes = Vec::with_capacity(
+            Self::HEADER_LEN + entries.len() * (Self::KEY_LEN + kind.value_len()),
+        );
+        bytes.extend_from_slice(Self::MAGIC);
+        bytes.extend_from_slice(&[kind.tag(), 0, 0, 0]);
+        bytes.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        for (board, weights) in entries {
+            let (left, right) = board.as_pair();
+            bytes.extend_from_slice(&left.to_le_bytes());
+            bytes.extend_from_slice(&right.to_le_bytes());
+            match kind {
+                PolicyKind::BestColumn => bytes.push(best_column(&board, &weights) as u8),
+                PolicyKind::Weights => {
+                    for weight in weights {
+                        bytes.extend_from_slice(&weight.to_le_bytes());
+                    }
+                }
+            }
+        }
+        Self { kind, bytes }
+    }
+
+    /// checks the header, the entries themselves are only read when looked up
+    pub fn from_bytes(bytes: Vec<u8>) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        if bytes.len() < Self::HEADER_LEN || &bytes[0..4] != Self::MAGIC {
+            return Err(invalid("not a policy file"));
+        }
+        let kind = PolicyKind::from_tag(bytes[4]).ok_or_else(|| invalid("unknown policy kind"))?;
+        let len = u32::from_le_bytes(bytes[8..12].try_into().expect("4 bytes")) as usize;
+        if bytes.len() != Self::HEADER_LEN + len * (Self::KEY_LEN + kind.value_len()) {
+            return Err(invalid(
+                "policy file doesn't have as many entries as it says",
+            ));
+        }
+        Ok(Self { kind, bytes })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn kind(&self) -> PolicyKind {
+        self.kind
+    }
+
+    fn entry_len(&self) -> usize {
+        Self::KEY_LEN + self.kind.value_len()
+    }
+
+    pub fn len(&self) -> usize {
+        (self.bytes.len() - Self::HEADER_LEN) / self.entry_len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn entry(&self, idx: usize) -> &[u8] {
+        let start = Self::HEADER_LEN + idx * self.entry_len();
+        &self.bytes[start..start + self.entry_len()]
+    }
+
+    fn key(entry: &[u8]) -> (u64, u32) {
+        (
+            u64::from_le_bytes(entry[0..8].try_into().expect("8 bytes")),
+            u32::from_le_bytes(entry[8..12].try_into().expect("4 bytes")),
+        )
+    }
+
+    pub fn lookup(&self, board: Board) -> Option<PolicyMove> {
+        let (key, swapped) = board.canonical();
+        let key = key.as_pair();
+        let (mut low, mut high) = (0, self.len());
+        let entry = loop {
+            if low >= high {
+                return None;
+            }
+            let middle = low + (high - low) / 2;
+            let entry = self.entry(middle);
+            match Self::key(entry).cmp(&key) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => break entry,
+            }
+        };
+        let value = &entry[Self::KEY_LEN..];
+        match self.kind {
+            PolicyKind::BestColumn => {
+                let column = value[0] as usize;
+                if column >= Board::COLUMN_LEN {
+                    return None;
+                }
+                Some(PolicyMove::Column(match swapped {
+                    true => Board::COLUMN_LEN - 1 - column,
+                    false => column,
+                }))
+            }
+            PolicyKind::Weights => {
+                let mut weights: [i16; Board::COLUMN_LEN] = std::array::from_fn(|column| {
+                    i16::from_le_bytes([value[column * 2], value[column * 2 + 1]])
+                });
+                if swapped {
+                    weights.reverse();
+                }
+                Some(PolicyMove::Weights(weights))
+            }
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, &self.bytes)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_bytes(fs::read(path)?)
+    }
+}
+
+/// the available column with the highest weight, the leftmost one on ties
+pub fn best_column(board: &Board, weights: &[i16; Board::COLUMN_LEN]) -> usize {
+    let available = board.available_column_choices();
+    (0..Board::COLUMN_LEN)
+        .filter(|&column| available[column])
+        .max_by_key(|&column| (weights[column], std::cmp::Reverse(column)))
+        .expect("the board has a column left")
+}
+
+/// plays from an exported policy, and asks `fallback` about boards that
+/// aren't in it
+pub struct PolicyPlayer<P: Player> {
+    policy: CompactPolicy,
+    fallback: P,
+}
+
+impl<P: Player> PolicyPlayer<P> {
+    pub fn new(policy: CompactPolicy, fallback: P) -> Self {
+        Self { policy, fallback }
+    }
+
+    pub fn fallback(&mut self) -> &mut P {
+        &mut self.fallback
+    }
+
+    fn lookup(&self, board: Board) -> Option<(usize, Option<i16>)> {
+        match self.policy.lookup(board)? {
+            PolicyMove::Column(column) => Some((column, None)),
+            PolicyMove::Weights(weights) => {
+                let column = best_column(&board, &weights);
+                Some((column, Some(weights[column])))
+            }
+        }
+    }
+}
+
+impl<P: Player> Player for PolicyPlayer<P> {
+    fn choose_column(&mut self, board: Board, turn: Chip) -> usize {
+        self.choose_with_score(board, turn).0
+    }
+
+    fn choose_with_score(&mut self, board: Board, turn: Chip) -> (usize, Option<i16>) {
+        match self.lookup(board) {
+            Some(chosen) => chosen,
+            None => self.fallback.choose_with_score(board, turn),
+        }
+    }
+
+    fn search(
+        &mut self,
+        board: Board,
+        turn: Chip,
+        limits: SearchLimits,
+        report: &mut dyn FnMut(&SearchInfo),
+    ) -> (usize, Option<i16>) {
+        match self.lookup(board) {
+            Some(chosen) => chosen,
+            None => self.fallback.search(board, turn, limits, report),
+        }
+    }
+
+    fn hint(&mut self, board: Board, turn: Chip) -> (usize, Option<i16>) {
+        match self.lookup(board) {
+            Some(chosen) => chosen,
+            None => self.fallback.hint(board, turn),
+        }
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> bool {
+        self.fallback.set_option(name, value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        board::{Board, Chip},
+        bot::{Bot, BotTrainerGameResult},
+        player::{MinmaxPlayer, Player},
+    };
+
+    use super::{best_column, CompactPolicy, PolicyKind, PolicyMove, PolicyPlayer};
+
+    fn trained_bot() -> Bot {
+        let (mut red, mut yellow) = (Bot::new(50, 1), Bot::new(50, 2));
+        BotTrainerGameResult::new(&mut red, &mut yellow).start_with_iterations(200);
+        red
+    }
+
+    #[test]
+    fn lookup() {
+        let bot = trained_bot();
+        let columns = CompactPolicy::from_bot(&bot, PolicyKind::BestColumn);
+        let weights = CompactPolicy::from_bot(&bot, PolicyKind::Weights);
+        assert!(!columns.is_empty());
+        assert_eq!(columns.len(), weights.len());
+
+        for (board, expected) in bot.memory().filter(|(board, _)| !board.filled()) {
+            assert_eq!(weights.lookup(board), Some(PolicyMove::Weights(expected)));
+            // ties are broken on the canonical board, so any of the best
+            // columns will do
+            let Some(PolicyMove::Column(column)) = columns.lookup(board) else {
+                panic!("{board:?} should be in the policy");
+            };
+            assert_eq!(expected[column], expected[best_column(&board, &expected)]);
+
+            // a symmetric board is its own mirror image
+            if board.swap() == board {
+                continue;
+            }
+            let mut mirrored = expected;
+            mirrored.reverse();
+            assert_eq!(
+                weights.lookup(board.swap()),
+                Some(PolicyMove::Weights(mirrored))
+            );
+        }
+        let unseen = Board::from_moves("000000").unwrap();
+        assert!(bot.weights(unseen).is_none());
+        assert_eq!(columns.lookup(unseen), None);
+    }
+
+    #[test]
+    fn serde() {
+        let policy = CompactPolicy::from_bot(&trained_bot(), PolicyKind::Weights);
+        let result = CompactPolicy::from_bytes(policy.as_bytes().to_vec()).unwrap();
+        assert_eq!(result, policy);
+
+        let mut bytes = policy.as_bytes().to_vec();
+        bytes.pop();
+        assert!(CompactPolicy::from_bytes(bytes).is_err());
+        assert!(CompactPolicy::from_bytes(b"C4BK\0\0\0\0\0\0\0\0".to_vec()).is_err());
+    }
+
+    #[test]
+    fn player() {
+        let bot = trained_bot();
+        let policy = CompactPolicy::from_bot(&bot, PolicyKind::Weights);
+        let mut player = PolicyPlayer::new(policy, MinmaxPlayer::new(1));
+
+        let board = Board::new();
+        let weights = bot.weights(board).unwrap();
+        let (column, score) = player.choose_with_score(board, Chip::Red);
+        assert_eq!(column, best_column(&board, &weights));
+        assert_eq!(score, Some(weights[column]));
+
+        // off the policy, minmax gets to decide
+        let board = Board::from_moves("000000").unwrap();
+        assert_eq!(
+            player.choose_with_score(board, Chip::Red),
+            MinmaxPlayer::new(1).choose_with_score(board, Chip::Red)
+        );
+    }
+}
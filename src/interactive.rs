@@ -2,14 +2,50 @@
 use std::io::{self, Write};
 
 use crate::{
-    board::{Board, Chip, PlaceChipError},
+    board::{Board, Chip, ForcedMove, Minmaxxing, PlaceChipError},
     bot::Bot,
+    player::{MinmaxPlayer, Player},
 };
 
+/// who makes the moves for one of the colours
+pub enum Participant<'engine> {
+    Human,
+    Engine(&'engine mut dyn Player),
+}
+
 pub struct InteractiveGame {
     board: Board,
     turn: Chip,
-    moves: usize,
+    history: Vec<usize>,
+    hint_depth: u8,
+}
+
+enum Command {
+    Place(usize),
+    Hint,
+    Eval,
+    Undo,
+    ShowThreats,
+    Help,
+    Quit,
+    Invalid(String),
+}
+
+impl Command {
+    fn parse(input: &str) -> Self {
+        match input {
+            "hint" => Command::Hint,
+            "eval" => Command::Eval,
+            "undo" => Command::Undo,
+            "show threats" | "threats" => Command::ShowThreats,
+            "help" => Command::Help,
+            "quit" | "exit" => Command::Quit,
+            input => match input.parse() {
+                Ok(column) => Command::Place(column),
+                Err(_) => Command::Invalid(input.to_string()),
+            },
+        }
+    }
 }
 
 impl InteractiveGame {
@@ -17,10 +53,17 @@ impl InteractiveGame {
         Self {
             board: Board::new(),
             turn: Chip::Red,
-            moves: 0,
+            history: Vec::new(),
+            hint_depth: Board::MINMAX_DEPTH,
         }
     }
 
+    /// how deep minmax searches when asked for a `hint` or `eval`
+    pub fn with_hint_depth(mut self, hint_depth: u8) -> Self {
+        self.hint_depth = hint_depth;
+        self
+    }
+
     fn next_turn(&mut self) {
         self.turn = match self.turn {
             Chip::Red => Chip::Yellow,
@@ -28,187 +71,247 @@ impl InteractiveGame {
         }
     }
 
-    pub fn start_against_minmax(mut self) {
-        println!("{}", self.board);
+    fn read_command(&self) -> Command {
+        println!();
+        print!("Which column would you like to place your chip? (0-6, or 'help') % ");
+        io::stdout()
+            .lock()
+            .flush()
+            .expect("should be able to flush stdout");
+        let mut input = String::new();
+        let read = io::stdin()
+            .read_line(&mut input)
+            .expect("should be able to read line from stdin");
+        println!();
+        if read == 0 {
+            return Command::Quit;
+        }
+        Command::parse(input.trim())
+    }
 
+    /// prompts until the human picks a column, handling any other commands
+    /// along the way. hints come from `engine`, the one the human plays
+    /// against, if there is one. `None` if the human quit
+    fn human_turn(
+        &mut self,
+        undo_plies: usize,
+        mut engine: Option<&mut dyn Player>,
+    ) -> Option<usize> {
         loop {
-            let column = match self.turn {
-                Chip::Red => {
-                    println!();
-                    print!("Which column would you like to place your chip? (0-6) % ");
-                    io::stdout()
-                        .lock()
-                        .flush()
-                        .expect("should be able to flush stdout");
-                    let mut column = String::new();
-                    io::stdin()
-                        .read_line(&mut column)
-                        .expect("should be able to read line from stdin");
-                    println!();
+            match self.read_command() {
+                Command::Place(column) => break Some(column),
+                Command::Hint => self.print_hint(
+                    engine
+                        .as_mut()
+                        .map(|engine| &mut **engine as &mut dyn Player),
+                ),
+                Command::Eval => self.print_eval(),
+                Command::Undo => self.undo(undo_plies),
+                Command::ShowThreats => self.print_threats(),
+                Command::Help => Self::print_help(),
+                Command::Quit => break None,
+                Command::Invalid(input) => println!("Invalid column '{input}'"),
+            }
+        }
+    }
 
-                    let column = column.trim();
-                    let Ok(column) = column.parse() else {
-                        println!("Invalid column '{column}'");
-                        continue;
-                    };
-                    column
-                }
-                Chip::Yellow => {
-                    let (column, score) = match self.board.minmax(Chip::Yellow, self.turn) {
-                        crate::board::Minmaxxing::Result(_) => unreachable!("game is not over"),
-                        crate::board::Minmaxxing::Position(column, score) => (column, score),
-                    };
-                    println!();
-                    println!("The bot chose '{column}' with score '{score}'");
-                    println!();
-                    column
-                }
-            };
-            let placed_row = match self.board.place_chip(column, self.turn) {
-                Ok(v) => v,
-                Err(err) => {
-                    let msg = match err {
-                        PlaceChipError::ColumnOccupied => {
-                            format!("Column '{column}' is full, pick another column")
-                        }
-
-                        PlaceChipError::InvalidColumn => format!("Invalid column '{column}'"),
-                    };
-                    println!("{msg}");
-                    continue;
-                }
-            };
-            self.next_turn();
-            self.moves += 1;
-            println!("{}", self.board);
-            if self.moves > 6 {
-                if let Some(winner) = self.board.winner(column, placed_row) {
-                    match winner {
-                        Chip::Red => println!("Player won!"),
-                        Chip::Yellow => println!("Bot won!"),
-                    }
-                    break;
+    fn print_help() {
+        println!("0-6           place your chip in that column");
+        println!("hint          ask the engine for the best column");
+        println!("eval          show how the position looks to minmax");
+        println!("undo          take back your last move");
+        println!("show threats  mark the cells that would connect four");
+        println!("quit          leave the game");
+    }
+
+    /// asks `engine` what it would play in the human's place, or minmax when
+    /// there is no engine to ask
+    fn print_hint(&self, engine: Option<&mut dyn Player>) {
+        match self.board.forced_move(self.turn) {
+            Some(ForcedMove::Win(column)) => println!("'{column}' wins right away"),
+            Some(ForcedMove::Block(column)) => println!("'{column}' has to be blocked"),
+            Some(ForcedMove::Lost) => println!("Too many threats to block them all"),
+            None => (),
+        }
+        let Some(engine) = engine else {
+            match self
+                .board
+                .minmax_with_depth(self.turn, self.turn, self.hint_depth)
+            {
+                Minmaxxing::Result(_) => unreachable!("game is not over"),
+                Minmaxxing::Position(column, score) => {
+                    println!("Minmax suggests '{column}' with score '{score}'")
                 }
             }
-            if self.board.filled() {
-                println!("Tied!");
-                break;
+            return;
+        };
+        match engine.hint(self.board, self.turn) {
+            (column, Some(score)) => println!("The bot suggests '{column}' with score '{score}'"),
+            (column, None) => println!("The bot suggests '{column}'"),
+        }
+    }
+
+    fn print_eval(&self) {
+        let scores = self
+            .board
+            .minmax_scores(self.turn, self.turn, self.hint_depth);
+        println!(
+            "Board value for {:?}: {}",
+            self.turn,
+            self.board.value_of_board(self.turn)
+        );
+        for (column, score) in scores.into_iter().enumerate() {
+            match score {
+                Some(score) => println!("'{column}': {score}"),
+                None => println!("'{column}': full"),
             }
         }
     }
 
-    pub fn start_against_bot(mut self, bot: &mut Bot) {
+    fn print_threats(&self) {
+        let marks = self.board.threat_marks();
+        if marks.is_empty() {
+            println!("No threats");
+            return;
+        }
+        println!("{}", self.board.with_marks(&marks));
+    }
+
+    /// takes back `plies` moves
+    fn undo(&mut self, plies: usize) {
+        if self.history.len() < plies {
+            println!("Nothing to undo");
+            return;
+        }
+        let len = self.history.len().saturating_sub(plies);
+        self.history.truncate(len);
+        self.board = Board::new();
+        for &column in &self.history {
+            self.board
+                .place_chip(column, self.board.turn())
+                .expect("replaying moves that were already played");
+        }
+        self.turn = self.board.turn();
         println!("{}", self.board);
+    }
 
-        loop {
-            let column = match self.turn {
-                Chip::Red => {
-                    println!();
-                    print!("Which column would you like to place your chip? (0-6) % ");
-                    io::stdout()
-                        .lock()
-                        .flush()
-                        .expect("should be able to flush stdout");
-                    let mut column = String::new();
-                    io::stdin()
-                        .read_line(&mut column)
-                        .expect("should be able to read line from stdin");
-                    println!();
+    fn place_chip(&mut self, column: usize) -> Option<usize> {
+        match self.board.place_chip(column, self.turn) {
+            Ok(row) => {
+                self.history.push(column);
+                Some(row)
+            }
+            Err(err) => {
+                let msg = match err {
+                    PlaceChipError::ColumnOccupied => {
+                        format!("Column '{column}' is full, pick another column")
+                    }
 
-                    let column = column.trim();
-                    let Ok(column) = column.parse() else {
-                        println!("Invalid column '{column}'");
-                        continue;
+                    PlaceChipError::InvalidColumn => format!("Invalid column '{column}'"),
+                };
+                println!("{msg}");
+                None
+            }
+        }
+    }
+
+    /// plays `human` against minmax
+    pub fn start_against_minmax(self, human: Chip) {
+        let mut minmax = MinmaxPlayer::default();
+        self.start_against_engine(&mut minmax, human);
+    }
+
+    /// plays `human` against `bot`
+    pub fn start_against_bot(self, bot: &mut Bot, human: Chip) {
+        self.start_against_engine(bot, human);
+    }
+
+    pub fn start_against_engine(self, engine: &mut dyn Player, human: Chip) {
+        match human {
+            Chip::Red => self.play(Participant::Human, Participant::Engine(engine)),
+            Chip::Yellow => self.play(Participant::Engine(engine), Participant::Human),
+        }
+    }
+
+    /// two engines play each other while the human watches
+    pub fn spectate(self, red: &mut dyn Player, yellow: &mut dyn Player) {
+        self.play(Participant::Engine(red), Participant::Engine(yellow));
+    }
+
+    /// two humans take turns on the same terminal
+    pub fn start(self) {
+        self.play(Participant::Human, Participant::Human);
+    }
+
+    pub fn play<'engine>(
+        mut self,
+        mut red: Participant<'engine>,
+        mut yellow: Participant<'engine>,
+    ) {
+        let humans = [&red, &yellow]
+            .into_iter()
+            .filter(|participant| matches!(participant, Participant::Human))
+            .count();
+        // when playing an engine, undo takes back the engine's reply as well
+        let undo_plies = if humans == 1 { 2 } else { 1 };
+
+        println!("{}", self.board);
+
+        loop {
+            let (participant, opponent) = match self.turn {
+                Chip::Red => (&mut red, &mut yellow),
+                Chip::Yellow => (&mut yellow, &mut red),
+            };
+            let column = match participant {
+                Participant::Human => {
+                    let engine = match opponent {
+                        Participant::Engine(engine) => Some(&mut **engine as &mut dyn Player),
+                        Participant::Human => None,
+                    };
+                    let Some(column) = self.human_turn(undo_plies, engine) else {
+                        break;
                     };
                     column
                 }
-                Chip::Yellow => {
-                    let column = bot.choose(self.board).column;
+                Participant::Engine(engine) => {
+                    let (column, score) = engine.choose_with_score(self.board, self.turn);
                     println!();
-                    println!("The bot chose '{column}'");
+                    match score {
+                        Some(score) => println!(
+                            "The bot ({:?}) chose '{column}' with score '{score}'",
+                            self.turn
+                        ),
+                        None => println!("The bot ({:?}) chose '{column}'", self.turn),
+                    }
                     println!();
                     column
                 }
             };
-            let placed_row = match self.board.place_chip(column, self.turn) {
-                Ok(v) => v,
-                Err(err) => {
-                    let msg = match err {
-                        PlaceChipError::ColumnOccupied => {
-                            format!("Column '{column}' is full, pick another column")
-                        }
-
-                        PlaceChipError::InvalidColumn => format!("Invalid column '{column}'"),
-                    };
-                    println!("{msg}");
-                    continue;
-                }
-            };
-            self.next_turn();
-            self.moves += 1;
-            println!("{}", self.board);
-            if self.moves > 6 {
-                if let Some(winner) = self.board.winner(column, placed_row) {
-                    match winner {
-                        Chip::Red => println!("Player won!"),
-                        Chip::Yellow => println!("Bot won!"),
-                    }
+            let Some(placed_row) = self.place_chip(column) else {
+                if let Participant::Engine(_) = participant {
+                    println!("The bot ({:?}) made an invalid move, stopping", self.turn);
                     break;
                 }
-            }
-            if self.board.filled() {
-                println!("Tied!");
-                break;
-            }
-        }
-    }
-
-    pub fn start(mut self) {
-        println!("{}", self.board);
-
-        loop {
-            println!();
-            print!("Which column would you like to place your chip? (0-6) % ");
-            io::stdout()
-                .lock()
-                .flush()
-                .expect("should be able to flush stdout");
-            let mut column = String::new();
-            io::stdin()
-                .read_line(&mut column)
-                .expect("should be able to read line from stdin");
-            println!();
-
-            let column = column.trim();
-            let Ok(column) = column.parse() else {
-                println!("Invalid column '{column}'");
                 continue;
             };
-            let placed_row = match self.board.place_chip(column, self.turn) {
-                Ok(v) => v,
-                Err(err) => {
-                    let msg = match err {
-                        PlaceChipError::ColumnOccupied => {
-                            format!("Column '{column}' is full, pick another column")
-                        }
-
-                        PlaceChipError::InvalidColumn => format!("Invalid column '{column}'"),
-                    };
-                    println!("{msg}");
-                    continue;
-                }
-            };
             self.next_turn();
-            self.moves += 1;
             println!("{}", self.board);
-            if self.moves > 6 {
+            if self.history.len() > 6 {
                 if let Some(winner) = self.board.winner(column, placed_row) {
-                    println!("{:?}", winner);
+                    let (winner, loser) = match winner {
+                        Chip::Red => (&red, &yellow),
+                        Chip::Yellow => (&yellow, &red),
+                    };
+                    match (winner, loser) {
+                        (Participant::Human, Participant::Engine(_)) => println!("Player won!"),
+                        (Participant::Engine(_), Participant::Human) => println!("Bot won!"),
+                        _ => println!("{:?} won!", self.turn.opposite()),
+                    }
                     break;
                 }
             }
             if self.board.filled() {
-                println!("tie");
+                println!("Tied!");
                 break;
             }
         }
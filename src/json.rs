@@ -0,0 +1,511 @@
+#![allow(dead_code)]
+use std::fmt::Display;
+
+/// just enough json for talking to other tools
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum JsonError {
+    UnexpectedEnd,
+    UnexpectedChar(usize, char),
+    InvalidNumber(usize),
+    InvalidEscape(usize),
+    TrailingCharacters(usize),
+    /// arrays and objects nested deeper than `Parser::MAX_DEPTH`
+    TooDeep(usize),
+    /// valid json, but not what we expected to find in it
+    InvalidValue(String),
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonError::UnexpectedEnd => write!(f, "unexpected end of json"),
+            JsonError::UnexpectedChar(idx, char) => {
+                write!(f, "unexpected '{char}' at {idx}")
+            }
+            JsonError::InvalidNumber(idx) => write!(f, "invalid number at {idx}"),
+            JsonError::InvalidEscape(idx) => write!(f, "invalid escape at {idx}"),
+            JsonError::TrailingCharacters(idx) => write!(f, "trailing characters at {idx}"),
+            JsonError::TooDeep(idx) => write!(f, "nested too deep at {idx}"),
+            JsonError::InvalidValue(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+/// types that can be written out as json for other tools
+pub trait ToJson {
+    fn to_json(&self) -> Json;
+}
+
+/// types that can be read back from what their `ToJson` wrote
+pub trait FromJson: Sized {
+    fn from_json(json: &Json) -> Result<Self, JsonError>;
+
+    fn from_json_str(text: &str) -> Result<Self, JsonError> {
+        Self::from_json(&Json::parse(text)?)
+    }
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Self, JsonError> {
+        let mut parser = Parser {
+            chars: text.char_indices().collect(),
+            idx: 0,
+            depth: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if let Some(&(idx, _)) = parser.chars.get(parser.idx) {
+            return Err(JsonError::TrailingCharacters(idx));
+        }
+        Ok(value)
+    }
+
+    pub fn object<const N: usize>(fields: [(&str, Json); N]) -> Self {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields
+                .iter()
+                .find(|(field, _)| field == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// like `get`, but a missing field is an error
+    pub fn field(&self, key: &str) -> Result<&Json, JsonError> {
+        self.get(key)
+            .ok_or_else(|| JsonError::InvalidValue(format!("missing field '{key}'")))
+    }
+
+    /// `convert` applied to the field, with an error naming the field if it
+    /// is missing or `convert` gives `None`
+    pub fn field_as<'json, T>(
+        &'json self,
+        key: &str,
+        convert: impl FnOnce(&'json Json) -> Option<T>,
+    ) -> Result<T, JsonError> {
+        convert(self.field(key)?)
+            .ok_or_else(|| JsonError::InvalidValue(format!("invalid field '{key}'")))
+    }
+
+    /// like `field_as`, but a missing field is `default` instead of an error
+    pub fn field_or<'json, T>(
+        &'json self,
+        key: &str,
+        default: T,
+        convert: impl FnOnce(&'json Json) -> Option<T>,
+    ) -> Result<T, JsonError> {
+        match self.get(key) {
+            Some(_) => self.field_as(key, convert),
+            None => Ok(default),
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    /// the number, if it is a whole number that fits
+    pub fn as_i64(&self) -> Option<i64> {
+        let number = self.as_f64()?;
+        if number.fract() != 0.0 || number.abs() > i64::MAX as f64 {
+            return None;
+        }
+        Some(number as i64)
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_i64()?.try_into().ok()
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(bool) => Some(*bool),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::String(value.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Self {
+        Json::String(value)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+macro_rules! json_from_number {
+    ($($number:ty),*) => {
+        $(impl From<$number> for Json {
+            fn from(value: $number) -> Self {
+                Json::Number(value as f64)
+            }
+        })*
+    };
+}
+
+json_from_number!(i16, i32, i64, u8, u32, u64, usize, f64);
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => Json::Null,
+        }
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(values: Vec<T>) -> Self {
+        Json::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+fn write_string(f: &mut std::fmt::Formatter<'_>, string: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for char in string.chars() {
+        match char {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            char if (char as u32) < 0x20 => write!(f, "\\u{:04x}", char as u32)?,
+            char => write!(f, "{char}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(bool) => write!(f, "{bool}"),
+            Json::Number(number) if !number.is_finite() => write!(f, "null"),
+            Json::Number(number) => write!(f, "{number}"),
+            Json::String(string) => write_string(f, string),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (idx, value) in values.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (idx, (key, value)) in fields.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<(usize, char)>,
+    idx: usize,
+    /// arrays and objects the parser is in
+    depth: usize,
+}
+
+impl Parser {
+    /// every nested array or object is a recursion, so this keeps hostile
+    /// input from running out of stack
+    const MAX_DEPTH: usize = 128;
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.idx).map(|&(_, char)| char)
+    }
+
+    fn next(&mut self) -> Result<(usize, char), JsonError> {
+        let next = *self.chars.get(self.idx).ok_or(JsonError::UnexpectedEnd)?;
+        self.idx += 1;
+        Ok(next)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        match self.next()? {
+            (_, char) if char == expected => Ok(()),
+            (idx, char) => Err(JsonError::UnexpectedChar(idx, char)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.idx += 1;
+        }
+    }
+
+    fn literal(&mut self, literal: &str, value: Json) -> Result<Json, JsonError> {
+        for expected in literal.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, JsonError> {
+        self.skip_whitespace();
+        match self.peek().ok_or(JsonError::UnexpectedEnd)? {
+            'n' => self.literal("null", Json::Null),
+            't' => self.literal("true", Json::Bool(true)),
+            'f' => self.literal("false", Json::Bool(false)),
+            '"' => Ok(Json::String(self.string()?)),
+            '[' => self.nested(Self::array),
+            '{' => self.nested(Self::object),
+            '-' | '0'..='9' => self.number(),
+            char => Err(JsonError::UnexpectedChar(self.chars[self.idx].0, char)),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Json, JsonError>,
+    ) -> Result<Json, JsonError> {
+        if self.depth == Self::MAX_DEPTH {
+            return Err(JsonError::TooDeep(self.chars[self.idx].0));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    /// how many digits were skipped
+    fn digits(&mut self) -> usize {
+        let start = self.idx;
+        while self.peek().is_some_and(|char| char.is_ascii_digit()) {
+            self.idx += 1;
+        }
+        self.idx - start
+    }
+
+    /// `-?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?`
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let start = self.idx;
+        let invalid = JsonError::InvalidNumber(self.chars[start].0);
+        if self.peek() == Some('-') {
+            self.idx += 1;
+        }
+        match self.peek() {
+            Some('0') => self.idx += 1,
+            Some('1'..='9') => {
+                self.digits();
+            }
+            _ => return Err(invalid),
+        }
+        if self.peek() == Some('.') {
+            self.idx += 1;
+            if self.digits() == 0 {
+                return Err(invalid);
+            }
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            self.idx += 1;
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.idx += 1;
+            }
+            if self.digits() == 0 {
+                return Err(invalid);
+            }
+        }
+        let number: String = self.chars[start..self.idx]
+            .iter()
+            .map(|&(_, char)| char)
+            .collect();
+        number.parse().map(Json::Number).map_err(|_| invalid)
+    }
+
+    /// the 4 hex digits of a `\u` escape
+    fn hex(&mut self, escape: usize) -> Result<u32, JsonError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.next()?.1.to_digit(16);
+            code = code * 16 + digit.ok_or(JsonError::InvalidEscape(escape))?;
+        }
+        Ok(code)
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.next()? {
+                (_, '"') => break Ok(string),
+                (idx, '\\') => {
+                    let char = match self.next()?.1 {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => {
+                            let mut code = self.hex(idx)?;
+                            // characters outside the basic plane are escaped as a
+                            // pair of surrogates
+                            if (0xd800..0xdc00).contains(&code) {
+                                match (self.next(), self.next()) {
+                                    (Ok((_, '\\')), Ok((_, 'u'))) => (),
+                                    _ => return Err(JsonError::InvalidEscape(idx)),
+                                }
+                                let low = self.hex(idx)?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err(JsonError::InvalidEscape(idx));
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            char::from_u32(code).ok_or(JsonError::InvalidEscape(idx))?
+                        }
+                        _ => return Err(JsonError::InvalidEscape(idx)),
+                    };
+                    string.push(char);
+                }
+                (_, char) => string.push(char),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, JsonError> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.idx += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.next()? {
+                (_, ',') => continue,
+                (_, ']') => break Ok(Json::Array(values)),
+                (idx, char) => break Err(JsonError::UnexpectedChar(idx, char)),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, JsonError> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.idx += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.next()? {
+                (_, ',') => continue,
+                (_, '}') => break Ok(Json::Object(fields)),
+                (idx, char) => break Err(JsonError::UnexpectedChar(idx, char)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Json, JsonError, Parser};
+
+    #[test]
+    fn parse_serialize() {
+        let text = r#"{"column":3,"name":"a \"b\"\n","nested":[true,null,-1.5,{}],"empty":[]}"#;
+        let json = Json::parse(text).unwrap();
+        assert_eq!(json.get("column").and_then(Json::as_usize), Some(3));
+        assert_eq!(json.get("name").and_then(Json::as_str), Some("a \"b\"\n"));
+        assert_eq!(json.to_string(), text);
+    }
+
+    #[test]
+    fn whitespace_and_escapes() {
+        let json = Json::parse(" { \"a\" : [ 1 , 2 ] , \"b\" : \"\\u0041\" } ").unwrap();
+        assert_eq!(
+            json,
+            Json::object([("a", Json::from(vec![1, 2])), ("b", Json::from("A")),])
+        );
+        assert_eq!(
+            Json::parse(r#""\ud83e\udd89""#),
+            Ok(Json::from("\u{1f989}"))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Json::parse(""), Err(JsonError::UnexpectedEnd));
+        assert_eq!(Json::parse("[1,]"), Err(JsonError::UnexpectedChar(3, ']')));
+        assert_eq!(Json::parse("{} x"), Err(JsonError::TrailingCharacters(3)));
+        assert_eq!(Json::parse("-"), Err(JsonError::InvalidNumber(0)));
+        for number in ["01", "1.", "1e", "1e+", ".5", "+1", "-a"] {
+            assert!(Json::parse(number).is_err(), "{number}");
+        }
+        assert_eq!(Json::parse("-0.5e+2"), Ok(Json::Number(-50.0)));
+        assert_eq!(Json::parse("\"\\ud800\""), Err(JsonError::InvalidEscape(1)));
+        assert_eq!(
+            Json::parse(&"[".repeat(Parser::MAX_DEPTH + 1)),
+            Err(JsonError::TooDeep(Parser::MAX_DEPTH))
+        );
+        assert!(Json::parse(&"[".repeat(65536)).is_err());
+        assert_eq!(Json::get(&Json::Null, "a"), None);
+    }
+}
@@ -1,172 +1,450 @@
 #![allow(dead_code)]
-use board::Chip;
-use bot::{
-    Bot, BotTrainerBoardPosition, BotTrainerGameResult, Game, GladiatorBotTrainer, MinMaxBotTrainer,
+use std::{
+    io::{BufRead, ErrorKind, Write},
+    time::{Duration, Instant},
 };
-use interactive::InteractiveGame;
 
+use analysis::Analysis;
+use arena::{Arena, EngineProcess};
+use bench::{Bencher, Report};
+use board::{Board, Chip};
+use book::OpeningBook;
+use bot::Bot;
+use evolution::{EvolutionConfig, EvolutionTrainer};
+use experiment::Experiment;
+use export::{CompactPolicy, PolicyKind};
+use interactive::{InteractiveGame, Participant};
+use json::ToJson;
+use merge::MergeStrategy;
+use network::NetworkGame;
+use neural::{Network, SelfPlayTrainer};
+use player::{player_from_spec, MinmaxPlayer, Player};
+use protocol::Protocol;
+use rand::Rand;
+use server::Server;
+use stats::Sprt;
+use tui::TuiGame;
+
+mod analysis;
+mod arena;
+mod bench;
 mod board;
+mod book;
 mod bot;
+mod evaluator;
+mod evolution;
+mod experiment;
+mod export;
 mod interactive;
+mod json;
+mod merge;
+mod network;
+mod neural;
+mod player;
+mod policy;
+mod protocol;
+mod rand;
+mod reward;
+mod server;
+mod stats;
+mod tactics;
+mod tui;
 
-fn test_bot_vs_bot(bot_1: &mut Bot, bot_2: &mut Bot) -> (i32, i32, i32) {
-    let mut ties = 0;
-    let mut bot_1_wins = 0;
-    let mut bot_2_wins = 0;
-    for _ in 0..10000 {
-        let mut game = Game::new();
-        loop {
-            let player = match game.turn {
-                Chip::Red => &mut *bot_1,
-                Chip::Yellow => &mut *bot_2,
-            };
-            let choice = player.choose(game.board);
-            let column = choice.column;
+fn generate_opening_book(path: &str, ply: usize, depth: u8) {
+    println!("generating opening book to ply {ply} at depth {depth}...");
+    let book = OpeningBook::generate(ply, depth);
+    book.save(path)
+        .expect("should be able to write opening book");
+    println!("wrote {} positions to '{path}'", book.len());
+}
 
-            let placed_row = match game.board.place_chip(column, game.turn) {
-                Ok(v) => v,
-                Err(_) => {
-                    unreachable!("our bot is perfect B)");
-                }
-            };
-            if let Some(winner) = game.board.winner(column, placed_row) {
-                debug_assert!(winner == game.turn);
-                match game.turn {
-                    Chip::Red => bot_1_wins += 1,
-                    Chip::Yellow => bot_2_wins += 1,
-                };
-                break;
-            } else if game.board.filled() {
-                ties += 1;
-                break;
-            }
-            game.next_turn();
+fn analyze_position(moves: &str, depth: u8, bot_path: Option<&str>, json: bool) {
+    let board = match Board::from_moves(moves) {
+        Ok(board) => board,
+        Err(err) => {
+            eprintln!("invalid moves '{moves}': {err:?}");
+            std::process::exit(1);
         }
-        std::mem::swap(bot_1, bot_2);
-        std::mem::swap(&mut bot_1_wins, &mut bot_2_wins)
+    };
+    let bot = bot_path.map(|path| Bot::load(path, 0, 0).expect("should be able to load bot"));
+    let analysis = Analysis::new(board, bot.as_ref(), depth);
+    if json {
+        println!("{}", analysis.to_json());
+    } else {
+        println!("{analysis}");
     }
-    (ties, bot_1_wins, bot_2_wins)
 }
 
-fn bot_vs_bot_and_loss() {
-let mut seed = 0x80085;
-    let mut red = Bot::new(50, seed);
-    let mut yellow = Bot::new(50, 0x58008);
-    let check_loss_times = 1000;
-    let iterations = 100_000_000;
-    let mut last_red_bot = red.clone();
-let mut test_results: Vec<(i32, i32, i32)> = Vec::new();
-
-    for i in 0..check_loss_times {
-        let trainer = BotTrainerBoardPosition::new(&mut red, &mut yellow);
-        trainer.start_with_iterations(iterations / check_loss_times);
-
-        red.exploration = 5;
-        let test_result = test_bot_vs_bot(&mut red, &mut last_red_bot);
-test_results.push(test_result);
-        red.exploration = 50;
+fn merge_bots(strategy: MergeStrategy, path: &str, bot_paths: &[&str]) {
+    let bots: Vec<_> = bot_paths
+        .iter()
+        .map(|path| Bot::load(path, 0, 0).expect("should be able to load bot"))
+        .collect();
+    let merged = merge::merge(&bots, strategy);
+    merged.save(path).expect("should be able to save bot");
+    println!(
+        "merged {} bots with {} boards into {} boards at {path}",
+        bots.len(),
+        bots.iter().map(Bot::memory_len).sum::<usize>(),
+        merged.memory_len()
+    );
+}
 
-        println!(
-            "current_bot_win_rate: {}, old_bot_win_rate: {}",
-            test_result.1 * 100 / (test_result.2 + test_result.1 + test_result.0),
-            test_result.2 * 100 / (test_result.2 + test_result.1 + test_result.0)
-        );
-        if i % (check_loss_times / 100) == 0 {
-            last_red_bot = red.clone();
-            seed += 1;
-            last_red_bot.change_seed(seed);
-            last_red_bot.exploration = 5;
-            let sum_test_result: (i32, i32, i32) =
-                test_results.iter().fold((0, 0, 0), |left, right| {
-                    (left.0 + right.0, left.1 + right.1, left.2 + right.2)
-                });
-            let average_test_result = (
-                sum_test_result.0 / test_results.len() as i32,
-                sum_test_result.1 / test_results.len() as i32,
-                sum_test_result.2 / test_results.len() as i32,
-            );
+fn export_policy(bot_path: &str, path: &str, kind: PolicyKind) {
+    let bot = Bot::load(bot_path, 0, 0).expect("should be able to load bot");
+    let policy = CompactPolicy::from_bot(&bot, kind);
+    policy.save(path).expect("should be able to save policy");
+    println!(
+        "exported {} of {} boards to {path}, {} bytes",
+        policy.len(),
+        bot.memory_len(),
+        policy.as_bytes().len()
+    );
+}
 
-            println!(
-                "average_current_bot_win_rate: {}, average_old_bot_win_rate: {}",
-                average_test_result.1 * 100 / (test_result.2 + test_result.1 + test_result.0),
-                average_test_result.2 * 100 / (test_result.2 + test_result.1 + test_result.0)
-            );
+fn perft(depth: u8, moves: &str) {
+    let board = match Board::from_moves(moves) {
+        Ok(board) => board,
+        Err(err) => {
+            eprintln!("invalid moves '{moves}': {err:?}");
+            std::process::exit(1);
         }
+    };
+    for depth in 0..=depth {
+        let started = Instant::now();
+        let nodes = board.perft(depth);
+        let elapsed = started.elapsed();
+        let per_second = nodes as f64 / elapsed.as_secs_f64().max(1e-9);
+        println!("perft {depth}: {nodes} ({elapsed:?}, {per_second:.0} nodes/s)");
     }
 }
 
-fn player_vs_trained_bot_learning_from_game_result() {
-    let mut red = Bot::new(50, 0x80085);
-    let mut yellow = Bot::new(50, 0x58008);
-    let iterations = 1_000_000;
+fn run_benchmarks(filter: Option<&str>, baseline: Option<&str>, save: Option<&str>) {
+    let mut bencher = Bencher::new(Duration::from_secs(1), filter.map(str::to_string));
+    bench::run(&mut bencher);
+    let report = bencher.finish();
+    let baseline =
+        baseline.map(|path| Report::load(path).expect("should be able to load baseline"));
 
-    let trainer = BotTrainerGameResult::new(&mut red, &mut yellow);
-    trainer.start_with_iterations(iterations);
-    let game = InteractiveGame::new();
-    red.exploration = 5;
-    game.start_against_bot(&mut red);
+    println!();
+    let changes = match &baseline {
+        Some(baseline) => report.changes(baseline),
+        None => report.measurements.iter().map(|v| (v, None)).collect(),
+    };
+    for (measurement, change) in changes {
+        let change = match change {
+            Some(change) if change < -Report::TOLERANCE => {
+                format!("{:+.1}% slower!", change * 100.0)
+            }
+            Some(change) => format!("{:+.1}%", change * 100.0),
+            None => String::new(),
+        };
+        println!(
+            "{:<24}{:>16.1} {}/s {change}",
+            measurement.name, measurement.per_second, measurement.unit
+        );
+    }
+    if let Some(path) = save {
+        report.save(path).expect("should be able to save baseline");
+        println!("saved to {path}");
+    }
+    if baseline.is_some_and(|baseline| !report.regressions(&baseline).is_empty()) {
+        std::process::exit(2);
+    }
 }
 
-fn player_vs_trained_bot_learning_from_board_positions() {
-    let mut red = Bot::new(50, 0x80085);
-    let mut yellow = Bot::new(50, 0x58008);
-    let iterations = 1_000_000;
+fn run_arena(
+    red: &str,
+    yellow: &str,
+    games: usize,
+    movetime: u64,
+    records_path: &str,
+    sprt: Option<Sprt>,
+) {
+    let command =
+        |command: &str| -> Vec<String> { command.split_whitespace().map(str::to_string).collect() };
+    let mut first = EngineProcess::spawn(&command(red)).expect("should be able to start engine");
+    let mut second =
+        EngineProcess::spawn(&command(yellow)).expect("should be able to start engine");
+    let mut records = std::io::BufWriter::new(
+        std::fs::File::create(records_path).expect("should be able to create records file"),
+    );
 
-    let trainer = BotTrainerBoardPosition::new(&mut red, &mut yellow);
-    trainer.start_with_iterations(iterations);
-    red.exploration = 5;
-    loop {
-        let game = InteractiveGame::new();
-        game.start_against_bot(&mut red);
-    }
+    let mut arena = Arena::new(std::time::Duration::from_millis(movetime));
+    arena.json_records = records_path.ends_with(".json") || records_path.ends_with(".jsonl");
+    arena.sprt = sprt;
+    let stats = arena
+        .play_match(&mut first, &mut second, games, &mut records)
+        .expect("should be able to write game records");
+    println!("'{red}' against '{yellow}'");
+    println!("{stats}");
 }
 
-fn player_vs_gladiator() {
-    let iterations = 1_000;
+fn serve(address: &str, engine: &str) {
+    let server = match engine.split_once(':') {
+        // bots are loaded once, every game gets its own copy
+        Some(("bot", path)) => {
+            let bot = Bot::load(path, 5, 0x80085).expect("should be able to load bot");
+            Server::new(move || Ok(Box::new(bot.clone())))
+        }
+        _ => {
+            if let Err(err) = player_from_spec(engine) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+            let engine = engine.to_string();
+            Server::new(move || player_from_spec(&engine))
+        }
+    };
+    server
+        .serve(address)
+        .expect("should be able to listen on address");
+}
 
-    let trainer = GladiatorBotTrainer::new(1000);
-    let mut bot = trainer.the_one_bot_to_rule_them_all(iterations);
-    let game = InteractiveGame::new();
-    game.start_against_bot(&mut bot);
+fn evolve(generations: usize, population: usize, path: &str) {
+    let config = EvolutionConfig {
+        population,
+        ..EvolutionConfig::default()
+    };
+    let references: Vec<Box<dyn Player>> = vec![
+        Box::new(MinmaxPlayer::new(1)),
+        Box::new(MinmaxPlayer::new(3)),
+    ];
+    let (best, bot) = EvolutionTrainer::new(config, references, 0x80085).evolve(generations);
+    println!("fittest: {:.3} ({})", best.fitness, best.genome);
+    bot.save(path).expect("should be able to save bot");
 }
 
-fn player_vs_trained_minmax_bot() {
-    let mut red = Bot::new(50, 0x80085);
-    let iterations = 10_000;
+fn run_experiment(path: &str) {
+    let experiment = Experiment::load(path).expect("should be able to read experiment");
+    println!("running '{}'...", experiment.name);
+    let mut log: Box<dyn Write> = match &experiment.log_path {
+        Some(log_path) => Box::new(std::io::BufWriter::new(
+            std::fs::File::create(log_path).expect("should be able to create log file"),
+        )),
+        None => Box::new(std::io::stdout()),
+    };
+    let (bot, checkpoints) = experiment
+        .run(&mut log)
+        .expect("should be able to run experiment");
+    log.flush().expect("should be able to write log");
+    if let Some(last) = checkpoints.last() {
+        println!("after {} iterations:", last.iteration);
+        println!("{}", last.stats);
+    }
+    println!("trained {} positions", bot.memory_len());
+}
 
-    let trainer = MinMaxBotTrainer::new(&mut red);
-    trainer.start_with_iterations(iterations);
-    let game = InteractiveGame::new();
-    red.exploration = 5;
-    game.start_against_bot(&mut red);
+fn train_network(games: usize, path: &str) {
+    let network = match Network::load(path) {
+        Ok(network) => {
+            println!("continuing from '{path}'");
+            network
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => Network::new(64, &mut Rand::new(0x80085)),
+        Err(err) => {
+            eprintln!("can't load '{path}': {err}");
+            std::process::exit(1);
+        }
+    };
+    let mut trainer = SelfPlayTrainer::new(network, 0x58008);
+    let checks = 10.min(games).max(1);
+    for check in 1..=checks {
+        // the last check also plays the games that don't divide evenly
+        let chunk = if check == checks {
+            games - games / checks * (checks - 1)
+        } else {
+            games / checks
+        };
+        let loss = trainer.train(chunk);
+        println!("{}%: loss {loss:.4}", check * 100 / checks);
+    }
+    trainer
+        .network
+        .save(path)
+        .expect("should be able to save network");
 }
 
-fn player_vs_minmax_bot() {
-    let game = InteractiveGame::new();
-    game.start_against_minmax();
+fn engine_from_spec(spec: &str) -> Option<Box<dyn Player>> {
+    player_from_spec(spec)
+        .map_err(|err| eprintln!("{err}"))
+        .ok()
 }
 
-fn trained_bot_learning_from_game_result_vs_trained_bot_learning_from_board_positions() {
-    let mut red_game_result_bot = Bot::new(50, 0x80085);
-    let mut yellow_game_result_bot = Bot::new(50, 0x58008);
-    let iterations = 10_000_000;
+/// full-screen when running in a terminal, line by line otherwise
+fn play<'engine>(mut red: Participant<'engine>, mut yellow: Participant<'engine>) {
+    if TuiGame::supported() {
+        match TuiGame::new().play(&mut red, &mut yellow) {
+            Ok(()) => return,
+            Err(err) => eprintln!("can't set up the terminal, playing line by line: {err}"),
+        }
+    }
+    InteractiveGame::new().play(red, yellow);
+}
 
-    let trainer = BotTrainerGameResult::new(&mut red_game_result_bot, &mut yellow_game_result_bot);
-    trainer.start_with_iterations(iterations);
+fn play_network<R: BufRead, W: Write>(game: NetworkGame<R, W>) {
+    game.play(std::io::stdin().lock(), std::io::stdout().lock())
+        .expect("should be able to talk to the other player");
+}
 
-    let mut red_board_position_bot = Bot::new(50, 0x80085);
-    let mut yellow_board_position_bot = Bot::new(50, 0x58008);
+fn chip_from_name(name: &str) -> Option<Chip> {
+    match name {
+        "red" => Some(Chip::Red),
+        "yellow" => Some(Chip::Yellow),
+        _ => None,
+    }
+}
 
-    let trainer =
-        BotTrainerBoardPosition::new(&mut red_board_position_bot, &mut yellow_board_position_bot);
-    trainer.start_with_iterations(iterations);
-    let test_result = test_bot_vs_bot(&mut red_game_result_bot, &mut yellow_board_position_bot);
-    println!(
-        "ties: {}, game_result_bot_wins: {}, board_position_wins: {}",
-        test_result.0, test_result.1, test_result.2
-    )
+fn usage() -> ! {
+    eprintln!("usage: connect-4-ai [book <path> <ply> <depth>]");
+    eprintln!("       connect-4-ai evolve <generations> <population> <path>");
+    eprintln!("       connect-4-ai experiment <config.json>");
+    eprintln!("       connect-4-ai train-net <games> <path>");
+    eprintln!("       connect-4-ai analyze <moves> <depth> [bot] [--json]");
+    eprintln!("       connect-4-ai merge <average|visits|confidence> <path> <bot>...");
+    eprintln!("       connect-4-ai export <bot> <path> [columns|weights]");
+    eprintln!("       connect-4-ai perft <depth> [moves]");
+    eprintln!("       connect-4-ai bench [filter] [--baseline <path>] [--save <path>]");
+    eprintln!("       connect-4-ai play <red|yellow> <engine>");
+    eprintln!("       connect-4-ai play human");
+    eprintln!("       connect-4-ai host <address> <red|yellow>");
+    eprintln!("       connect-4-ai join <address>");
+    eprintln!("       connect-4-ai spectate <engine> <engine>");
+    eprintln!("       connect-4-ai engine [engine]");
+    eprintln!("       connect-4-ai serve <address> [engine]");
+    eprintln!("       connect-4-ai arena <command> <command> <games> <movetime ms> <records>");
+    eprintln!("                   [--sprt]");
+    eprintln!();
+    eprintln!("engines: minmax, minmax:<depth>[:<evaluator>], bot:<path>, net:<path>,");
+    eprintln!("         netminmax:<depth>:<path>, policy:<path>, book:<path>:<engine>,");
+    eprintln!("         safe:<engine>");
+    eprintln!("evaluators: opportunities, threats, oddeven, net:<path>, bot:<path>");
+    std::process::exit(1);
 }
 
 fn main() {
-    bot_vs_bot_and_loss();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        [] => run_experiment("experiments/self_play.json"),
+        ["book", path, ply, depth] => {
+            let (Ok(ply), Ok(depth)) = (ply.parse(), depth.parse()) else {
+                usage();
+            };
+            generate_opening_book(path, ply, depth);
+        }
+        ["evolve", generations, population, path] => {
+            let (Ok(generations), Ok(population)) = (generations.parse(), population.parse())
+            else {
+                usage();
+            };
+            evolve(generations, population, path);
+        }
+        ["experiment", path] => run_experiment(path),
+        ["train-net", games, path] => {
+            let Ok(games) = games.parse() else {
+                usage();
+            };
+            train_network(games, path);
+        }
+        ["analyze", moves, depth, rest @ ..] => {
+            let json = rest.contains(&"--json");
+            let bot: Vec<_> = rest.iter().filter(|&&arg| arg != "--json").collect();
+            let (Ok(depth), true) = (depth.parse(), bot.len() <= 1) else {
+                usage();
+            };
+            analyze_position(moves, depth, bot.first().map(|bot| **bot), json);
+        }
+        ["bench", rest @ ..] => {
+            let (mut filter, mut baseline, mut save) = (None, None, None);
+            let mut rest = rest.iter();
+            while let Some(&arg) = rest.next() {
+                match arg {
+                    "--baseline" => baseline = rest.next().copied(),
+                    "--save" => save = rest.next().copied(),
+                    _ if filter.is_none() => filter = Some(arg),
+                    _ => usage(),
+                }
+            }
+            run_benchmarks(filter, baseline, save);
+        }
+        ["merge", strategy, path, bots @ ..] if !bots.is_empty() => {
+            let Ok(strategy) = strategy.parse() else {
+                usage();
+            };
+            merge_bots(strategy, path, bots);
+        }
+        ["export", bot, path, kind @ ..] if kind.len() <= 1 => {
+            let kind = match kind.first() {
+                None | Some(&"columns") => PolicyKind::BestColumn,
+                Some(&"weights") => PolicyKind::Weights,
+                Some(_) => usage(),
+            };
+            export_policy(bot, path, kind);
+        }
+        ["perft", depth, moves @ ..] if moves.len() <= 1 => {
+            let Ok(depth) = depth.parse() else {
+                usage();
+            };
+            perft(depth, moves.first().copied().unwrap_or(""));
+        }
+        ["play", "human"] => play(Participant::Human, Participant::Human),
+        ["play", human, engine] => {
+            let (Some(human), Some(mut engine)) = (chip_from_name(human), engine_from_spec(engine))
+            else {
+                usage();
+            };
+            match human {
+                Chip::Red => play(Participant::Human, Participant::Engine(engine.as_mut())),
+                Chip::Yellow => play(Participant::Engine(engine.as_mut()), Participant::Human),
+            }
+        }
+        ["host", address, local] => {
+            let Some(local) = chip_from_name(local) else {
+                usage();
+            };
+            let game = NetworkGame::host(address, local).expect("should be able to host a game");
+            play_network(game);
+        }
+        ["join", address] => {
+            let game = NetworkGame::join(address).expect("should be able to join the game");
+            play_network(game);
+        }
+        ["spectate", red, yellow] => {
+            let (Some(mut red), Some(mut yellow)) =
+                (engine_from_spec(red), engine_from_spec(yellow))
+            else {
+                usage();
+            };
+            play(
+                Participant::Engine(red.as_mut()),
+                Participant::Engine(yellow.as_mut()),
+            );
+        }
+        ["arena", red, yellow, games, movetime, records, sprt @ ..]
+            if matches!(sprt, [] | ["--sprt"]) =>
+        {
+            let (Ok(games), Ok(movetime)) = (games.parse(), movetime.parse()) else {
+                usage();
+            };
+            let sprt = (!sprt.is_empty()).then(Sprt::default);
+            run_arena(red, yellow, games, movetime, records, sprt);
+        }
+        ["serve", address, engine @ ..] if engine.len() <= 1 => {
+            serve(address, engine.first().copied().unwrap_or("minmax"))
+        }
+        ["engine", engine @ ..] if engine.len() <= 1 => {
+            let protocol = match Protocol::new(engine.first().copied().unwrap_or("minmax")) {
+                Ok(protocol) => protocol,
+                Err(err) => {
+                    eprintln!("{err}");
+                    usage();
+                }
+            };
+            protocol
+                .run(std::io::stdin().lock(), &mut std::io::stdout().lock())
+                .expect("should be able to talk over stdin/stdout");
+        }
+        _ => usage(),
+    }
 }
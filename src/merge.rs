@@ -0,0 +1,220 @@
+#![allow(dead_code)]
+use std::{collections::HashMap, str::FromStr};
+
+use crate::{board::Board, bot::Bot};
+
+/// how `merge` combines the weights of bots that have seen the same board
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergeStrategy {
+    /// the mean of each column's weights
+    Average,
+    /// the mean of each column's weights, weighted by how often each bot
+    /// picked the column. bots only count visits with the ucb policy, so
+    /// boards that any of the bots has no visits for are averaged instead
+    VisitWeighted,
+    /// all the weights of the bot that is most sure about the board, the one
+    /// with the biggest gap between its best and worst column
+    MaxConfidence,
+}
+
+impl FromStr for MergeStrategy {
+    type Err = String;
+
+    fn from_str(strategy: &str) -> Result<Self, Self::Err> {
+        match strategy {
+            "average" => Ok(MergeStrategy::Average),
+            "visits" => Ok(MergeStrategy::VisitWeighted),
+            "confidence" => Ok(MergeStrategy::MaxConfidence),
+            _ => Err(format!("unknown merge strategy '{strategy}'")),
+        }
+    }
+}
+
+type Entry = ([i16; Board::COLUMN_LEN], Option<[u32; Board::COLUMN_LEN]>);
+
+/// one bot that knows every board any of `bots` knows. a board and its
+/// mirror image are the same board, whichever of the two each bot remembers.
+/// the merged bot explores like the first one, and has the visits of all the
+/// bots added up, so it can be merged by visits again
+pub fn merge(bots: &[Bot], strategy: MergeStrategy) -> Bot {
+    let mut entries: HashMap<Board, Vec<Entry>> = HashMap::new();
+    for bot in bots {
+        for (board, mut weights) in bot.memory() {
+            let mut visits = bot.visits(board);
+            let (key, swapped) = board.canonical();
+            if swapped {
+                weights.reverse();
+                if let Some(visits) = &mut visits {
+                    visits.reverse();
+                }
+            }
+            entries.entry(key).or_default().push((weights, visits));
+        }
+    }
+
+    let mut merged = match bots.first() {
+        Some(first) => Bot::new(first.exploration, 0).with_policy(first.policy),
+        None => Bot::new(0, 0),
+    };
+    for (board, seen) in entries {
+        merged.set_weights(board, combine(&board, &seen, strategy));
+        let visits = seen
+            .iter()
+            .filter_map(|(_, visits)| *visits)
+            .reduce(|sum, visits| std::array::from_fn(|column| sum[column] + visits[column]));
+        if let Some(visits) = visits {
+            merged.set_visits(board, visits);
+        }
+    }
+    merged
+}
+
+fn combine(board: &Board, seen: &[Entry], strategy: MergeStrategy) -> [i16; Board::COLUMN_LEN] {
+    let average = |column: usize, weight_of: &dyn Fn(&Entry) -> u32| {
+        let (sum, total) = seen.iter().fold((0.0, 0.0), |(sum, total), entry| {
+            let weight = weight_of(entry) as f64;
+            (sum + entry.0[column] as f64 * weight, total + weight)
+        });
+        if total == 0.0 {
+            0
+        } else {
+            (sum / total).round() as i16
+        }
+    };
+    match strategy {
+        MergeStrategy::Average => std::array::from_fn(|column| average(column, &|_| 1)),
+        MergeStrategy::VisitWeighted => {
+            let visits: Option<Vec<_>> = seen.iter().map(|(_, visits)| *visits).collect();
+            std::array::from_fn(|column| match &visits {
+                Some(visits) if visits.iter().any(|visits| visits[column] > 0) => {
+                    average(column, &|entry| {
+                        entry.1.expect("every bot has visits for the board")[column]
+                    })
+                }
+                _ => average(column, &|_| 1),
+            })
+        }
+        MergeStrategy::MaxConfidence => {
+            let available = board.available_column_choices();
+            let confidence = |weights: &[i16; Board::COLUMN_LEN]| {
+                let weights = (0..Board::COLUMN_LEN)
+                    .filter(|&column| available[column])
+                    .map(|column| weights[column] as i32);
+                weights.clone().max().unwrap_or(0) - weights.min().unwrap_or(0)
+            };
+            seen.iter()
+                .map(|(weights, _)| *weights)
+                .reduce(
+                    |best, weights| match confidence(&weights) > confidence(&best) {
+                        true => weights,
+                        false => best,
+                    },
+                )
+                .expect("boards are only merged when a bot has them")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{board::Board, bot::Bot, policy::SelectionPolicy};
+
+    use super::{merge, MergeStrategy};
+
+    fn mirrored(mut weights: [i16; Board::COLUMN_LEN]) -> [i16; Board::COLUMN_LEN] {
+        weights.reverse();
+        weights
+    }
+
+    #[test]
+    fn average_with_mirrored_keys() {
+        let board = Board::from_moves("01").unwrap();
+        let (mut first, mut second) = (Bot::new(10, 1), Bot::new(20, 2));
+        first.set_weights(board, [10, 0, 0, 0, 0, 0, -10]);
+        // the second bot only knows the mirror image
+        second.set_weights(board.swap(), mirrored([20, 4, 0, 0, 0, 0, 0]));
+        second.set_weights(Board::new(), [1; 7]);
+
+        let merged = merge(&[first, second], MergeStrategy::Average);
+        assert_eq!(merged.memory_len(), 2);
+        assert_eq!(merged.exploration, 10);
+        assert_eq!(merged.weights(board), Some([15, 2, 0, 0, 0, 0, -5]));
+        assert_eq!(
+            merged.weights(board.swap()),
+            Some(mirrored([15, 2, 0, 0, 0, 0, -5]))
+        );
+        assert_eq!(merged.weights(Board::new()), Some([1; 7]));
+    }
+
+    #[test]
+    fn max_confidence() {
+        let board = Board::from_moves("3").unwrap();
+        let (mut first, mut second) = (Bot::new(10, 1), Bot::new(10, 2));
+        first.set_weights(board, [0, 0, 5, 6, 5, 0, 0]);
+        second.set_weights(board, [-50, 0, 0, 40, 0, 0, -50]);
+
+        let merged = merge(&[first, second], MergeStrategy::MaxConfidence);
+        assert_eq!(merged.weights(board), Some([-50, 0, 0, 40, 0, 0, -50]));
+    }
+
+    #[test]
+    fn visit_weighted() {
+        let board = Board::new();
+        let mut visited = Bot::new(0, 1).with_policy(SelectionPolicy::Ucb { c: 0.0 });
+        visited.set_weights(board, [0, 0, 0, 100, 0, 0, 0]);
+        // ucb tries every column once, then keeps playing the best
+        for _ in 0..Board::COLUMN_LEN + 3 {
+            visited.choose(board);
+            visited.clear_played_choices();
+        }
+        assert_eq!(visited.visits(board), Some([1, 1, 1, 4, 1, 1, 1]));
+
+        let mut also_visited = Bot::new(0, 2).with_policy(SelectionPolicy::Ucb { c: 0.0 });
+        also_visited.set_weights(board, [0, 0, 0, 0, 0, 0, 70]);
+        for _ in 0..Board::COLUMN_LEN + 3 {
+            also_visited.choose(board);
+            also_visited.clear_played_choices();
+        }
+
+        let merged = merge(
+            &[visited.clone(), also_visited],
+            MergeStrategy::VisitWeighted,
+        );
+        assert_eq!(merged.weights(board), Some([0, 0, 0, 80, 0, 0, 56]));
+        assert_eq!(merged.visits(board), Some([2, 2, 2, 5, 2, 2, 5]));
+        assert!(merged.weights(Board::from_moves("3").unwrap()).is_none());
+
+        // merged bots keep their visits, so they can be weighed by them again
+        let remerged = merge(&[merged, visited.clone()], MergeStrategy::VisitWeighted);
+        assert_eq!(remerged.weights(board), Some([0, 0, 0, 89, 0, 0, 47]));
+        assert_eq!(remerged.visits(board), Some([3, 3, 3, 9, 3, 3, 6]));
+
+        // without visits from every bot, there is nothing to weigh by
+        let mut other = Bot::new(0, 3);
+        other.set_weights(board, [10, 10, 10, 0, 10, 10, 10]);
+        let merged = merge(
+            &[visited.clone(), other.clone()],
+            MergeStrategy::VisitWeighted,
+        );
+        assert_eq!(merged.weights(board), Some([5, 5, 5, 50, 5, 5, 5]));
+        assert_eq!(merged.visits(board), visited.visits(board));
+        let merged = merge(&[other], MergeStrategy::VisitWeighted);
+        assert_eq!(merged.visits(board), None);
+
+        // visits of a mirror image are mirrored into the merged bot
+        let opening = Board::from_moves("0").unwrap();
+        let mut mirrored_visits = Bot::new(0, 4);
+        mirrored_visits.set_weights(opening.swap(), [0; 7]);
+        mirrored_visits.set_visits(opening.swap(), [1, 2, 3, 4, 5, 6, 7]);
+        let merged = merge(&[mirrored_visits], MergeStrategy::VisitWeighted);
+        assert_eq!(merged.visits(opening), Some([7, 6, 5, 4, 3, 2, 1]));
+    }
+
+    #[test]
+    fn strategies() {
+        assert_eq!("average".parse(), Ok(MergeStrategy::Average));
+        assert_eq!("visits".parse(), Ok(MergeStrategy::VisitWeighted));
+        assert_eq!("confidence".parse(), Ok(MergeStrategy::MaxConfidence));
+        assert!("vibes".parse::<MergeStrategy>().is_err());
+    }
+}
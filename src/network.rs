@@ -0,0 +1,397 @@
+#![allow(dead_code)]
+use std::{
+    fmt::Display,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+};
+
+use crate::board::{Board, Chip, PlaceChipError};
+
+/// one line of the network protocol. the host greets the guest with `hello`,
+/// after that both sides send `move`s on their turn, or `resign` at any point
+/// they would otherwise move
+///
+/// ```text
+/// host  > hello 1 yellow
+/// host  > move 3
+/// guest > move 3
+/// guest > resign
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    /// protocol version and the colour of whoever receives it
+    Hello(u32, Chip),
+    Move(usize),
+    Resign,
+    /// the other side sent something it should not have, the game is off
+    Error(String),
+}
+
+impl Message {
+    pub const VERSION: u32 = 1;
+
+    pub fn parse(line: &str) -> Option<Self> {
+        let words: Vec<_> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["hello", version, chip] => Some(Message::Hello(
+                version.parse().ok()?,
+                match *chip {
+                    "red" => Chip::Red,
+                    "yellow" => Chip::Yellow,
+                    _ => return None,
+                },
+            )),
+            ["move", column] => Some(Message::Move(column.parse().ok()?)),
+            ["resign"] => Some(Message::Resign),
+            ["error", msg @ ..] => Some(Message::Error(msg.join(" "))),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Message::Hello(version, Chip::Red) => write!(f, "hello {version} red"),
+            Message::Hello(version, Chip::Yellow) => write!(f, "hello {version} yellow"),
+            Message::Move(column) => write!(f, "move {column}"),
+            Message::Resign => write!(f, "resign"),
+            Message::Error(msg) => write!(f, "error {msg}"),
+        }
+    }
+}
+
+/// how a network game ended
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Won(Chip),
+    Tie,
+    Resigned(Chip),
+    /// the other side went away mid game
+    Disconnected,
+    /// the other side broke the protocol or made an illegal move
+    Aborted(String),
+}
+
+/// a game between two humans on different terminals, each running one of these
+pub struct NetworkGame<R: BufRead, W: Write> {
+    board: Board,
+    local: Chip,
+    peer_reader: R,
+    peer_writer: W,
+}
+
+impl NetworkGame<BufReader<TcpStream>, TcpStream> {
+    /// waits for a guest on `address`, the host plays `local`
+    pub fn host(address: impl ToSocketAddrs, local: Chip) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        println!(
+            "Waiting for an opponent on {}, playing {local:?}",
+            listener.local_addr()?
+        );
+        let (stream, peer) = listener.accept()?;
+        println!("{peer} joined");
+        let mut game = Self::from_stream(stream, local)?;
+        game.send(&Message::Hello(Message::VERSION, local.opposite()))?;
+        Ok(game)
+    }
+
+    /// joins a game hosted on `address`, the host decides who plays which colour
+    pub fn join(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        let mut game = Self::from_stream(stream, Chip::Red)?;
+        match game.receive()? {
+            Some(Message::Hello(Message::VERSION, local)) => game.local = local,
+            Some(Message::Hello(version, _)) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("the host speaks version '{version}'"),
+                ))
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "the host did not say hello",
+                ))
+            }
+        }
+        println!("Joined, playing {:?}", game.local);
+        Ok(game)
+    }
+
+    fn from_stream(stream: TcpStream, local: Chip) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        Ok(Self::new(
+            BufReader::new(stream.try_clone()?),
+            stream,
+            local,
+        ))
+    }
+}
+
+impl<R: BufRead, W: Write> NetworkGame<R, W> {
+    pub fn new(peer_reader: R, peer_writer: W, local: Chip) -> Self {
+        Self {
+            board: Board::new(),
+            local,
+            peer_reader,
+            peer_writer,
+        }
+    }
+
+    fn send(&mut self, message: &Message) -> io::Result<()> {
+        writeln!(self.peer_writer, "{message}")?;
+        self.peer_writer.flush()
+    }
+
+    /// `None` once the other side hung up
+    fn receive(&mut self) -> io::Result<Option<Message>> {
+        let mut line = String::new();
+        match self.peer_reader.read_line(&mut line) {
+            Ok(0) => return Ok(None),
+            Ok(_) => (),
+            Err(err) if err.kind() == io::ErrorKind::ConnectionReset => return Ok(None),
+            Err(err) => return Err(err),
+        }
+        match Message::parse(&line) {
+            Some(message) => Ok(Some(message)),
+            None => Ok(Some(Message::Error(format!(
+                "unknown message '{}'",
+                line.trim()
+            )))),
+        }
+    }
+
+    /// prompts on `output` until the local player picks a legal column,
+    /// `None` if they resign
+    fn local_turn(
+        &self,
+        input: &mut impl BufRead,
+        output: &mut impl Write,
+    ) -> io::Result<Option<usize>> {
+        loop {
+            writeln!(output)?;
+            write!(
+                output,
+                "Which column would you like to place your chip? (0-6, or 'resign') % "
+            )?;
+            output.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            writeln!(output)?;
+            let line = line.trim();
+            if matches!(line, "resign" | "quit" | "exit") {
+                return Ok(None);
+            }
+            let Ok(column) = line.parse() else {
+                writeln!(output, "Invalid column '{line}'")?;
+                continue;
+            };
+            // dry run, the move is only played once it was sent
+            let mut board = self.board;
+            match board.place_chip(column, board.turn()) {
+                Ok(_) => return Ok(Some(column)),
+                Err(PlaceChipError::ColumnOccupied) => {
+                    writeln!(output, "Column '{column}' is full, pick another column")?
+                }
+                Err(PlaceChipError::InvalidColumn) => {
+                    writeln!(output, "Invalid column '{column}'")?
+                }
+            }
+        }
+    }
+
+    /// waits for the other side's move, `Err` with the outcome if the game
+    /// ended instead
+    fn peer_turn(&mut self) -> io::Result<Result<usize, Outcome>> {
+        let outcome = match self.receive()? {
+            Some(Message::Move(column)) => return Ok(Ok(column)),
+            Some(Message::Resign) => Outcome::Resigned(self.local.opposite()),
+            None => Outcome::Disconnected,
+            Some(Message::Error(msg)) => Outcome::Aborted(msg),
+            Some(message) => {
+                let msg = format!("unexpected message '{message}'");
+                self.send(&Message::Error(msg.clone()))?;
+                Outcome::Aborted(msg)
+            }
+        };
+        Ok(Err(outcome))
+    }
+
+    /// plays until the game ends, with the local player typing into `input`
+    pub fn play(mut self, mut input: impl BufRead, mut output: impl Write) -> io::Result<Outcome> {
+        writeln!(output, "{}", self.board)?;
+        let outcome = loop {
+            let turn = self.board.turn();
+            let column = if turn == self.local {
+                let Some(column) = self.local_turn(&mut input, &mut output)? else {
+                    // the other side may be gone already, we are leaving anyway
+                    let _ = self.send(&Message::Resign);
+                    break Outcome::Resigned(self.local);
+                };
+                if self.send(&Message::Move(column)).is_err() {
+                    break Outcome::Disconnected;
+                }
+                column
+            } else {
+                writeln!(output, "Waiting for {turn:?} to move")?;
+                match self.peer_turn()? {
+                    Ok(column) => column,
+                    Err(outcome) => break outcome,
+                }
+            };
+
+            let row = match self.board.place_chip(column, turn) {
+                Ok(row) => row,
+                Err(_) => {
+                    let msg = format!("illegal move '{column}'");
+                    let _ = self.send(&Message::Error(msg.clone()));
+                    break Outcome::Aborted(msg);
+                }
+            };
+            if turn != self.local {
+                writeln!(output)?;
+                writeln!(output, "{turn:?} chose '{column}'")?;
+            }
+            writeln!(output, "{}", self.board)?;
+            if self.board.winner(column, row).is_some() {
+                break Outcome::Won(turn);
+            }
+            if self.board.filled() {
+                break Outcome::Tie;
+            }
+        };
+
+        match &outcome {
+            Outcome::Won(chip) if *chip == self.local => writeln!(output, "You won!")?,
+            Outcome::Won(chip) => writeln!(output, "{chip:?} won!")?,
+            Outcome::Tie => writeln!(output, "Tied!")?,
+            Outcome::Resigned(chip) if *chip == self.local => writeln!(output, "You resigned")?,
+            Outcome::Resigned(chip) => writeln!(output, "{chip:?} resigned, you won!")?,
+            Outcome::Disconnected => writeln!(output, "The other player disconnected")?,
+            Outcome::Aborted(msg) => writeln!(output, "The game was aborted: {msg}")?,
+        }
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use crate::board::Chip;
+
+    use super::{Message, NetworkGame, Outcome};
+
+    /// a host playing `host_moves` against a guest playing `guest_moves`
+    fn play(host_moves: &'static str, guest_moves: &'static str) -> (Outcome, Outcome) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let guest = thread::spawn(move || {
+            NetworkGame::join(address)
+                .unwrap()
+                .play(guest_moves.as_bytes(), Vec::new())
+                .unwrap()
+        });
+        let (stream, _) = listener.accept().unwrap();
+        let mut host = NetworkGame::new(
+            BufReader::new(stream.try_clone().unwrap()),
+            stream,
+            Chip::Red,
+        );
+        host.send(&Message::Hello(Message::VERSION, Chip::Yellow))
+            .unwrap();
+        let host = host.play(host_moves.as_bytes(), Vec::new()).unwrap();
+        (host, guest.join().unwrap())
+    }
+
+    #[test]
+    fn messages() {
+        for message in [
+            Message::Hello(1, Chip::Yellow),
+            Message::Move(3),
+            Message::Resign,
+            Message::Error("illegal move '7'".to_string()),
+        ] {
+            assert_eq!(Message::parse(&message.to_string()), Some(message));
+        }
+        assert_eq!(Message::parse("move x"), None);
+    }
+
+    #[test]
+    fn win() {
+        let (host, guest) = play("0\n0\n0\n0\n", "1\n1\n1\n");
+        assert_eq!(host, Outcome::Won(Chip::Red));
+        assert_eq!(guest, Outcome::Won(Chip::Red));
+    }
+
+    #[test]
+    fn local_moves_are_validated() {
+        // once the first column is full, playing into it, past the board and
+        // nonsense are rejected locally and never sent
+        let (host, guest) = play("0\n0\n0\n0\n7\nx\n1\n1\n1\n1\n", "0\n0\n0\n2\n2\n2\n");
+        assert_eq!(host, Outcome::Won(Chip::Red));
+        assert_eq!(guest, Outcome::Won(Chip::Red));
+    }
+
+    #[test]
+    fn resign() {
+        let (host, guest) = play("3\n", "resign\n");
+        assert_eq!(host, Outcome::Resigned(Chip::Yellow));
+        assert_eq!(guest, Outcome::Resigned(Chip::Yellow));
+    }
+
+    #[test]
+    fn disconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let guest = thread::spawn(move || {
+            NetworkGame::join(address)
+                .unwrap()
+                .play(&b""[..], Vec::new())
+                .unwrap()
+        });
+        let (stream, _) = listener.accept().unwrap();
+        writeln!(
+            &stream,
+            "{}",
+            Message::Hello(Message::VERSION, Chip::Yellow)
+        )
+        .unwrap();
+        drop(stream);
+        assert_eq!(guest.join().unwrap(), Outcome::Disconnected);
+    }
+
+    #[test]
+    fn illegal_peer_move() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let guest = thread::spawn(move || {
+            NetworkGame::join(address)
+                .unwrap()
+                .play(&b""[..], Vec::new())
+                .unwrap()
+        });
+        let (stream, _) = listener.accept().unwrap();
+        writeln!(
+            &stream,
+            "{}",
+            Message::Hello(Message::VERSION, Chip::Yellow)
+        )
+        .unwrap();
+        writeln!(&stream, "{}", Message::Move(9)).unwrap();
+        assert_eq!(
+            guest.join().unwrap(),
+            Outcome::Aborted("illegal move '9'".to_string())
+        );
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply).unwrap();
+        assert_eq!(reply, "error illegal move '9'\n");
+    }
+}
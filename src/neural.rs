@@ -0,0 +1,616 @@
+#![allow(dead_code)]
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    board::{Board, Chip, Minmaxxing},
+    evaluator::Evaluator,
+    player::Player,
+    rand::{Rand, Rng},
+};
+
+const CELLS: usize = Board::COLUMN_LEN * Board::ROW_LEN;
+/// two planes of 42 cells, the chips of whoever's turn it is, then the other's
+pub const INPUT_LEN: usize = 2 * CELLS;
+
+/// the board as the network sees it, from the side of whoever's turn it is
+pub fn planes(board: &Board) -> [f32; INPUT_LEN] {
+    let turn = board.turn();
+    let mut input = [0.0; INPUT_LEN];
+    for column in 0..Board::COLUMN_LEN {
+        for row in 0..Board::ROW_LEN {
+            let cell = column * Board::ROW_LEN + row;
+            match board.chip_at(column, row) {
+                Some(chip) if chip == turn => input[cell] = 1.0,
+                Some(_) => input[CELLS + cell] = 1.0,
+                None => (),
+            }
+        }
+    }
+    input
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Output {
+    /// odds of each column being the best move, 0 for full columns
+    pub policy: [f32; Board::COLUMN_LEN],
+    /// in `-1.0..=1.0`, how good the board is for whoever's turn it is
+    pub value: f32,
+}
+
+/// what the network should have said about a board
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub board: Board,
+    /// `None` to only learn the value
+    pub policy: Option<[f32; Board::COLUMN_LEN]>,
+    pub value: f32,
+}
+
+impl Sample {
+    /// the same lesson for the mirror image of the board
+    pub fn mirrored(&self) -> Self {
+        Self {
+            board: self.board.swap(),
+            policy: self.policy.map(|mut policy| {
+                policy.reverse();
+                policy
+            }),
+            value: self.value,
+        }
+    }
+}
+
+/// what the forward pass leaves behind for the backward pass
+struct Activations {
+    input: [f32; INPUT_LEN],
+    hidden: Vec<f32>,
+    policy: [f32; Board::COLUMN_LEN],
+    value: f32,
+}
+
+/// a multilayer perceptron with one hidden relu layer, a softmax policy head
+/// and a tanh value head. all parameters live in one vector, laid out as the
+/// hidden weights and biases, the policy weights and biases, then the value
+/// weights and bias
+#[derive(Debug, Clone, PartialEq)]
+pub struct Network {
+    hidden_len: usize,
+    params: Vec<f32>,
+}
+
+impl Network {
+    const MAGIC: &'static [u8; 4] = b"C4NN";
+
+    /// small random weights, scaled by how many inputs each layer has
+    pub fn new(hidden_len: usize, rand: &mut impl Rng) -> Self {
+        let mut network = Self {
+            hidden_len,
+            params: vec![0.0; Self::params_len(hidden_len)],
+        };
+        let hidden_scale = (6.0 / (INPUT_LEN + hidden_len) as f64).sqrt();
+        let head_scale = (6.0 / (hidden_len + Board::COLUMN_LEN) as f64).sqrt();
+        for idx in 0..network.params.len() {
+            let scale = if idx < network.policy_weights() {
+                hidden_scale
+            } else {
+                head_scale
+            };
+            let is_bias = (network.hidden_biases()..network.policy_weights()).contains(&idx)
+                || (network.policy_biases()..network.value_weights()).contains(&idx)
+                || idx == network.value_bias();
+            if !is_bias {
+                network.params[idx] = ((rand.next_f64() * 2.0 - 1.0) * scale) as f32;
+            }
+        }
+        network
+    }
+
+    fn params_len(hidden_len: usize) -> usize {
+        hidden_len * INPUT_LEN
+            + hidden_len
+            + Board::COLUMN_LEN * hidden_len
+            + Board::COLUMN_LEN
+            + hidden_len
+            + 1
+    }
+
+    fn hidden_biases(&self) -> usize {
+        self.hidden_len * INPUT_LEN
+    }
+
+    fn policy_weights(&self) -> usize {
+        self.hidden_biases() + self.hidden_len
+    }
+
+    fn policy_biases(&self) -> usize {
+        self.policy_weights() + Board::COLUMN_LEN * self.hidden_len
+    }
+
+    fn value_weights(&self) -> usize {
+        self.policy_biases() + Board::COLUMN_LEN
+    }
+
+    fn value_bias(&self) -> usize {
+        self.value_weights() + self.hidden_len
+    }
+
+    pub fn hidden_len(&self) -> usize {
+        self.hidden_len
+    }
+
+    fn forward(&self, board: &Board) -> Activations {
+        let input = planes(board);
+        let params = &self.params;
+        let hidden: Vec<f32> = (0..self.hidden_len)
+            .map(|neuron| {
+                let weights = &params[neuron * INPUT_LEN..(neuron + 1) * INPUT_LEN];
+                let sum: f32 = weights
+                    .iter()
+                    .zip(&input)
+                    .filter(|&(_, &x)| x != 0.0)
+                    .map(|(weight, x)| weight * x)
+                    .sum();
+                (sum + params[self.hidden_biases() + neuron]).max(0.0)
+            })
+            .collect();
+
+        let layer = |weights: usize, bias: usize| {
+            let weights = &params[weights..weights + self.hidden_len];
+            weights.iter().zip(&hidden).map(|(w, h)| w * h).sum::<f32>() + params[bias]
+        };
+        let logits: [f32; Board::COLUMN_LEN] = std::array::from_fn(|column| {
+            layer(
+                self.policy_weights() + column * self.hidden_len,
+                self.policy_biases() + column,
+            )
+        });
+        let value = layer(self.value_weights(), self.value_bias()).tanh();
+
+        // softmax over the columns that are not full
+        let available = board.available_column_choices();
+        let max = (0..Board::COLUMN_LEN)
+            .filter(|&column| available[column])
+            .map(|column| logits[column])
+            .fold(f32::NEG_INFINITY, f32::max);
+        let mut policy = std::array::from_fn(|column| {
+            if available[column] {
+                (logits[column] - max).exp()
+            } else {
+                0.0
+            }
+        });
+        let total: f32 = policy.iter().sum();
+        if total > 0.0 {
+            policy.iter_mut().for_each(|odds| *odds /= total);
+        }
+
+        Activations {
+            input,
+            hidden,
+            policy,
+            value,
+        }
+    }
+
+    pub fn evaluate(&self, board: &Board) -> Output {
+        let activations = self.forward(board);
+        Output {
+            policy: activations.policy,
+            value: activations.value,
+        }
+    }
+
+    /// cross entropy of the policy plus squared error of the value
+    fn loss(activations: &Activations, sample: &Sample) -> f32 {
+        let policy_loss = sample.policy.map_or(0.0, |target| {
+            target
+                .iter()
+                .zip(activations.policy)
+                .filter(|&(&target, _)| target > 0.0)
+                .map(|(target, odds)| -target * odds.max(1e-7).ln())
+                .sum()
+        });
+        policy_loss + (activations.value - sample.value).powi(2)
+    }
+
+    /// adds the gradient of the loss of `sample` to `gradient`
+    fn backward(&self, activations: &Activations, sample: &Sample, gradient: &mut [f32]) {
+        let hidden_len = self.hidden_len;
+        let logits_gradient: [f32; Board::COLUMN_LEN] = match sample.policy {
+            Some(target) => {
+                std::array::from_fn(|column| activations.policy[column] - target[column])
+            }
+            None => [0.0; Board::COLUMN_LEN],
+        };
+        let value = activations.value;
+        let value_gradient = 2.0 * (value - sample.value) * (1.0 - value * value);
+
+        let mut hidden_gradient = vec![0.0; hidden_len];
+        for (column, &logit_gradient) in logits_gradient.iter().enumerate() {
+            if logit_gradient == 0.0 {
+                continue;
+            }
+            let weights = self.policy_weights() + column * hidden_len;
+            for neuron in 0..hidden_len {
+                gradient[weights + neuron] += logit_gradient * activations.hidden[neuron];
+                hidden_gradient[neuron] += logit_gradient * self.params[weights + neuron];
+            }
+            gradient[self.policy_biases() + column] += logit_gradient;
+        }
+        for neuron in 0..hidden_len {
+            gradient[self.value_weights() + neuron] += value_gradient * activations.hidden[neuron];
+            hidden_gradient[neuron] += value_gradient * self.params[self.value_weights() + neuron];
+        }
+        gradient[self.value_bias()] += value_gradient;
+
+        for (neuron, &hidden_gradient) in hidden_gradient.iter().enumerate() {
+            // relu lets nothing through for neurons that were off
+            if activations.hidden[neuron] <= 0.0 {
+                continue;
+            }
+            let weights = neuron * INPUT_LEN;
+            for (idx, &x) in activations.input.iter().enumerate() {
+                if x != 0.0 {
+                    gradient[weights + idx] += hidden_gradient * x;
+                }
+            }
+            gradient[self.hidden_biases() + neuron] += hidden_gradient;
+        }
+    }
+
+    /// one step of gradient descent on the mean loss of `samples`, returning
+    /// that loss from before the step
+    pub fn train(&mut self, samples: &[Sample], learning_rate: f32) -> f32 {
+        if samples.is_empty() {
+            return 0.0;
+        }
+        let mut gradient = vec![0.0; self.params.len()];
+        let mut loss = 0.0;
+        for sample in samples {
+            let activations = self.forward(&sample.board);
+            loss += Self::loss(&activations, sample);
+            self.backward(&activations, sample, &mut gradient);
+        }
+        let scale = learning_rate / samples.len() as f32;
+        for (param, gradient) in self.params.iter_mut().zip(gradient) {
+            *param -= scale * gradient;
+        }
+        loss / samples.len() as f32
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(Self::MAGIC)?;
+        writer.write_all(&(self.hidden_len as u32).to_le_bytes())?;
+        for param in &self.params {
+            writer.write_all(&param.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != Self::MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a network file",
+            ));
+        }
+        let mut len = [0; 4];
+        reader.read_exact(&mut len)?;
+        let hidden_len = u32::from_le_bytes(len) as usize;
+
+        // the length is checked against what was read before anything is
+        // allocated for it, so a corrupt header can't ask for gigabytes
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        if hidden_len == 0 || bytes.len() != Self::params_len(hidden_len) * 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "network file has the wrong length",
+            ));
+        }
+        let params = bytes
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().expect("chunks of 4")))
+            .collect();
+        Ok(Self { hidden_len, params })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+}
+
+/// trains a network on games it plays against itself. every position learns
+/// the result of its game, and the moves of the winner are what the policy
+/// learns to play
+pub struct SelfPlayTrainer {
+    pub network: Network,
+    rand: Rand,
+    /// the most recent positions, which batches are drawn from
+    replay: Vec<Sample>,
+    replay_next: usize,
+    pub replay_len: usize,
+    pub batch_size: usize,
+    pub learning_rate: f32,
+    /// moves are sampled from the policy sharpened or flattened by this
+    pub temperature: f32,
+    /// odds of a uniformly random move instead
+    pub epsilon: f64,
+}
+
+impl SelfPlayTrainer {
+    pub fn new(network: Network, seed: usize) -> Self {
+        Self {
+            network,
+            rand: Rand::new(seed),
+            replay: Vec::new(),
+            replay_next: 0,
+            replay_len: 20_000,
+            batch_size: 64,
+            learning_rate: 0.05,
+            temperature: 1.0,
+            epsilon: 0.1,
+        }
+    }
+
+    fn choose(&mut self, board: &Board) -> usize {
+        let available = board.available_column_choices();
+        let columns: Vec<_> = (0..Board::COLUMN_LEN)
+            .filter(|&column| available[column])
+            .collect();
+        if self.rand.next_f64() < self.epsilon {
+            return columns[self.rand.below(columns.len())];
+        }
+        let policy = self.network.evaluate(board).policy;
+        let odds: Vec<f64> = columns
+            .iter()
+            .map(|&column| (policy[column] as f64).powf(1.0 / self.temperature.max(1e-3) as f64))
+            .collect();
+        let mut pick = self.rand.next_f64() * odds.iter().sum::<f64>();
+        for (&column, odds) in columns.iter().zip(&odds) {
+            if pick < *odds {
+                return column;
+            }
+            pick -= odds;
+        }
+        columns[columns.len() - 1]
+    }
+
+    /// one game of the network against itself, as lessons for every position
+    /// and its mirror image
+    pub fn play_game(&mut self) -> Vec<Sample> {
+        let mut board = Board::new();
+        let mut moves = Vec::new();
+        let winner = loop {
+            let turn = board.turn();
+            let column = self.choose(&board);
+            moves.push((board, column, turn));
+            let row = board
+                .place_chip(column, turn)
+                .expect("we only place based on available positions");
+            if board.winner(column, row).is_some() {
+                break Some(turn);
+            } else if board.filled() {
+                break None;
+            }
+        };
+
+        let mut samples = Vec::with_capacity(moves.len() * 2);
+        for (board, column, turn) in moves {
+            let won = winner == Some(turn);
+            let sample = Sample {
+                board,
+                policy: won.then(|| std::array::from_fn(|idx| (idx == column) as u8 as f32)),
+                value: match winner {
+                    Some(_) if won => 1.0,
+                    Some(_) => -1.0,
+                    None => 0.0,
+                },
+            };
+            samples.push(sample.mirrored());
+            samples.push(sample);
+        }
+        samples
+    }
+
+    fn remember(&mut self, samples: Vec<Sample>) {
+        for sample in samples {
+            if self.replay.len() < self.replay_len {
+                self.replay.push(sample);
+            } else {
+                self.replay[self.replay_next] = sample;
+                self.replay_next = (self.replay_next + 1) % self.replay_len;
+            }
+        }
+    }
+
+    /// plays `games` games, training on one batch after each. returns the
+    /// mean loss of the batches
+    pub fn train(&mut self, games: usize) -> f32 {
+        let mut loss = 0.0;
+        for _ in 0..games {
+            let samples = self.play_game();
+            self.remember(samples);
+            let batch: Vec<_> = (0..self.batch_size)
+                .map(|_| self.replay[self.rand.below(self.replay.len())])
+                .collect();
+            loss += self.network.train(&batch, self.learning_rate);
+        }
+        loss / games.max(1) as f32
+    }
+}
+
+/// plays the column the policy likes best, or searches `depth` plies with
+/// minmax and lets the value head judge the positions it ends on
+#[derive(Debug, Clone, PartialEq)]
+pub struct NeuralPlayer {
+    pub network: Network,
+    pub depth: Option<u8>,
+}
+
+impl NeuralPlayer {
+    pub fn new(network: Network, depth: Option<u8>) -> Self {
+        Self { network, depth }
+    }
+}
+
+impl Player for NeuralPlayer {
+    fn choose_column(&mut self, board: Board, turn: Chip) -> usize {
+        self.choose_with_score(board, turn).0
+    }
+
+    fn choose_with_score(&mut self, board: Board, turn: Chip) -> (usize, Option<i16>) {
+        if let Some(depth) = self.depth {
+            return match board.minmax_with_evaluator(turn, turn, depth, &self.network) {
+                Minmaxxing::Result(_) => unreachable!("game is not over"),
+                Minmaxxing::Position(column, score) => (column, Some(score)),
+            };
+        }
+        let output = self.network.evaluate(&board);
+        let available = board.available_column_choices();
+        let column = (0..Board::COLUMN_LEN)
+            .filter(|&column| available[column])
+            .max_by(|&left, &right| output.policy[left].total_cmp(&output.policy[right]))
+            .expect("game is not over");
+        (
+            column,
+            Some(Evaluator::evaluate(&self.network, &board, turn)),
+        )
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> bool {
+        match (name, value) {
+            ("depth", "none") => self.depth = None,
+            ("depth", depth) => match depth.parse() {
+                Ok(depth) => self.depth = Some(depth),
+                Err(_) => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        board::{Board, Chip},
+        player::Player,
+        rand::Rand,
+    };
+
+    use super::{planes, Network, NeuralPlayer, Sample, SelfPlayTrainer, INPUT_LEN};
+
+    fn sample() -> Sample {
+        Sample {
+            board: Board::from_moves("3342").unwrap(),
+            policy: Some([0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0]),
+            value: 0.5,
+        }
+    }
+
+    #[test]
+    fn planes_follow_the_turn() {
+        let board = Board::from_moves("4").unwrap();
+        let input = planes(&board);
+        assert_eq!(input.iter().sum::<f32>(), 1.0);
+        // yellow to move, so red's chip is in the second plane
+        assert_eq!(input[INPUT_LEN / 2 + 4 * Board::ROW_LEN], 1.0);
+    }
+
+    #[test]
+    fn gradient_matches_finite_differences() {
+        let network = Network::new(8, &mut Rand::new(1));
+        let sample = sample();
+        let mut gradient = vec![0.0; network.params.len()];
+        network.backward(&network.forward(&sample.board), &sample, &mut gradient);
+
+        let loss = |network: &Network| Network::loss(&network.forward(&sample.board), &sample);
+        for idx in (0..network.params.len()).step_by(37) {
+            let epsilon = 1e-3;
+            let mut up = network.clone();
+            up.params[idx] += epsilon;
+            let mut down = network.clone();
+            down.params[idx] -= epsilon;
+            let numeric = (loss(&up) - loss(&down)) / (2.0 * epsilon);
+            assert!(
+                (numeric - gradient[idx]).abs() < 1e-2,
+                "param {idx}: {numeric} vs {}",
+                gradient[idx]
+            );
+        }
+    }
+
+    #[test]
+    fn learns_a_position() {
+        let mut network = Network::new(16, &mut Rand::new(2));
+        let samples = [sample(), sample().mirrored()];
+        let first = network.train(&samples, 0.1);
+        for _ in 0..200 {
+            network.train(&samples, 0.1);
+        }
+        let last = network.train(&samples, 0.1);
+        assert!(last < first / 4.0, "{first} -> {last}");
+        let output = network.evaluate(&samples[0].board);
+        assert!(output.policy[5] > 0.8);
+        assert!((output.value - 0.5).abs() < 0.1);
+        // full columns never get picked
+        let full = Board::from_moves("111111").unwrap();
+        assert_eq!(network.evaluate(&full).policy[1], 0.0);
+    }
+
+    #[test]
+    fn save_load() {
+        let network = Network::new(4, &mut Rand::new(3));
+        let mut bytes = Vec::new();
+        network.write_to(&mut bytes).unwrap();
+        assert_eq!(Network::read_from(&mut bytes.as_slice()).unwrap(), network);
+        assert!(Network::read_from(&mut &bytes[..bytes.len() - 1]).is_err());
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(Network::read_from(&mut trailing.as_slice()).is_err());
+        let mut huge = bytes[..4].to_vec();
+        huge.extend(u32::MAX.to_le_bytes());
+        assert!(Network::read_from(&mut huge.as_slice()).is_err());
+        let mut empty = bytes[..4].to_vec();
+        empty.extend(0u32.to_le_bytes());
+        empty.resize(empty.len() + Network::params_len(0) * 4, 0);
+        assert!(Network::read_from(&mut empty.as_slice()).is_err());
+    }
+
+    #[test]
+    fn self_play() {
+        let mut trainer = SelfPlayTrainer::new(Network::new(8, &mut Rand::new(4)), 4);
+        let samples = trainer.play_game();
+        assert!(samples.len() >= 14 && samples.len().is_multiple_of(2));
+        assert!(samples.iter().all(|sample| sample.value.abs() <= 1.0));
+        assert!(trainer.train(5).is_finite());
+    }
+
+    #[test]
+    fn player() {
+        let network = Network::new(8, &mut Rand::new(5));
+        // red has three in column 1, whatever the network thinks
+        let board = Board::from_moves("121212").unwrap();
+        let mut searching = NeuralPlayer::new(network.clone(), Some(2));
+        assert_eq!(searching.choose_column(board, Chip::Red), 1);
+        let mut policy = NeuralPlayer::new(network, None);
+        let column = policy.choose_column(board, Chip::Red);
+        assert!(board.available_column_choices()[column]);
+        assert!(policy.set_option("depth", "3"));
+        assert_eq!(policy.depth, Some(3));
+    }
+}
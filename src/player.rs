@@ -0,0 +1,246 @@
+#![allow(dead_code)]
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board::{Board, Chip, Minmaxxing, SearchInfo},
+    book::{BookPlayer, OpeningBook},
+    bot::Bot,
+    evaluator::{evaluator_from_spec, Evaluator, Opportunities},
+    export::{best_column, CompactPolicy, PolicyPlayer},
+    neural::{Network, NeuralPlayer},
+    tactics::Tactical,
+};
+
+/// how long a player may think about a move, for players that search
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub movetime: Option<Duration>,
+}
+
+/// anything that can pick a column to play on a board
+pub trait Player {
+    fn choose_column(&mut self, board: Board, turn: Chip) -> usize;
+
+    /// the chosen column, along with the score the player gave it if it has one
+    fn choose_with_score(&mut self, board: Board, turn: Chip) -> (usize, Option<i16>) {
+        (self.choose_column(board, turn), None)
+    }
+
+    /// like `choose_with_score`, but respecting `limits` and calling `report`
+    /// whenever the search makes progress
+    fn search(
+        &mut self,
+        board: Board,
+        turn: Chip,
+        _limits: SearchLimits,
+        _report: &mut dyn FnMut(&SearchInfo),
+    ) -> (usize, Option<i16>) {
+        self.choose_with_score(board, turn)
+    }
+
+    /// what the player would play, without changing what it plays later, i.e.
+    /// for hints. players that learn or roll dice as they choose override it
+    fn hint(&mut self, board: Board, turn: Chip) -> (usize, Option<i16>) {
+        self.choose_with_score(board, turn)
+    }
+
+    /// changes a named setting, `false` if the player has no such setting or
+    /// the value is invalid
+    fn set_option(&mut self, _name: &str, _value: &str) -> bool {
+        false
+    }
+}
+
+impl<P: Player + ?Sized> Player for Box<P> {
+    fn choose_column(&mut self, board: Board, turn: Chip) -> usize {
+        (**self).choose_column(board, turn)
+    }
+
+    fn choose_with_score(&mut self, board: Board, turn: Chip) -> (usize, Option<i16>) {
+        (**self).choose_with_score(board, turn)
+    }
+
+    fn search(
+        &mut self,
+        board: Board,
+        turn: Chip,
+        limits: SearchLimits,
+        report: &mut dyn FnMut(&SearchInfo),
+    ) -> (usize, Option<i16>) {
+        (**self).search(board, turn, limits, report)
+    }
+
+    fn hint(&mut self, board: Board, turn: Chip) -> (usize, Option<i16>) {
+        (**self).hint(board, turn)
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> bool {
+        (**self).set_option(name, value)
+    }
+}
+
+impl Player for Bot {
+    fn choose_column(&mut self, board: Board, _turn: Chip) -> usize {
+        self.choose(board).column
+    }
+
+    fn choose_with_score(&mut self, board: Board, _turn: Chip) -> (usize, Option<i16>) {
+        let column = self.choose(board).column;
+        let score = self.weights(board).map(|weights| weights[column]);
+        (column, score)
+    }
+
+    /// its best column by the weights it has, without remembering the board
+    /// or counting a visit
+    fn hint(&mut self, board: Board, _turn: Chip) -> (usize, Option<i16>) {
+        let weights = self.weights(board).unwrap_or([0; Board::COLUMN_LEN]);
+        let column = best_column(&board, &weights);
+        (column, Some(weights[column]))
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> bool {
+        match name {
+            "exploration" => match value.parse() {
+                Ok(exploration) => self.exploration = exploration,
+                Err(_) => return false,
+            },
+            "seed" => match value.parse() {
+                Ok(seed) => self.change_seed(seed),
+                Err(_) => return false,
+            },
+            "policy" => match value.parse() {
+                Ok(policy) => self.policy = policy,
+                Err(_) => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+pub struct MinmaxPlayer {
+    pub depth: u8,
+    pub evaluator: Box<dyn Evaluator>,
+}
+
+impl MinmaxPlayer {
+    pub fn new(depth: u8) -> Self {
+        Self {
+            depth,
+            evaluator: Box::new(Opportunities),
+        }
+    }
+
+    pub fn with_evaluator(mut self, evaluator: Box<dyn Evaluator>) -> Self {
+        self.evaluator = evaluator;
+        self
+    }
+}
+
+impl Default for MinmaxPlayer {
+    fn default() -> Self {
+        Self::new(Board::MINMAX_DEPTH)
+    }
+}
+
+impl Player for MinmaxPlayer {
+    fn choose_column(&mut self, board: Board, turn: Chip) -> usize {
+        self.choose_with_score(board, turn).0
+    }
+
+    fn choose_with_score(&mut self, board: Board, turn: Chip) -> (usize, Option<i16>) {
+        match board.minmax_with_evaluator(turn, turn, self.depth, self.evaluator.as_ref()) {
+            Minmaxxing::Result(_) => unreachable!("game is not over"),
+            Minmaxxing::Position(column, score) => (column, Some(score)),
+        }
+    }
+
+    fn search(
+        &mut self,
+        board: Board,
+        turn: Chip,
+        limits: SearchLimits,
+        report: &mut dyn FnMut(&SearchInfo),
+    ) -> (usize, Option<i16>) {
+        // with only a movetime, it deepens for as long as it may
+        let (max_depth, deadline) = match (limits.depth, limits.movetime) {
+            (depth, Some(movetime)) => (depth.unwrap_or(u8::MAX), Some(Instant::now() + movetime)),
+            (Some(depth), None) => (depth, None),
+            (None, None) => (self.depth, None),
+        };
+        let info = board.minmax_deepening(
+            turn,
+            turn,
+            max_depth,
+            deadline,
+            self.evaluator.as_ref(),
+            report,
+        );
+        (info.column, Some(info.score))
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> bool {
+        match (name, value.parse()) {
+            ("depth", Ok(depth)) => self.depth = depth,
+            ("evaluator", _) => match evaluator_from_spec(value) {
+                Ok(evaluator) => self.evaluator = evaluator,
+                Err(_) => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// builds a player from a short description, one of `minmax`,
+/// `minmax:<depth>`, `minmax:<depth>:<evaluator>`, `bot:<path>`, `net:<path>`,
+/// `netminmax:<depth>:<path>`, `policy:<path>` for an exported policy,
+/// `book:<path>:<engine>`, which plays from an opening book before asking
+/// `engine`, or `safe:<engine>`, which keeps `engine` from missing or giving
+/// away immediate wins
+pub fn player_from_spec(spec: &str) -> io::Result<Box<dyn Player>> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unknown engine '{spec}'"),
+        )
+    };
+    match spec.split_once(':') {
+        None if spec == "minmax" => Ok(Box::new(MinmaxPlayer::default())),
+        Some(("minmax", rest)) => {
+            let (depth, evaluator) = match rest.split_once(':') {
+                Some((depth, evaluator)) => (depth, evaluator_from_spec(evaluator)?),
+                None => (rest, Box::new(Opportunities) as Box<dyn Evaluator>),
+            };
+            let depth = depth.parse().map_err(|_| invalid())?;
+            Ok(Box::new(MinmaxPlayer::new(depth).with_evaluator(evaluator)))
+        }
+        Some(("bot", path)) => Ok(Box::new(Bot::load(path, 5, 0x80085)?)),
+        Some(("net", path)) => Ok(Box::new(NeuralPlayer::new(Network::load(path)?, None))),
+        Some(("netminmax", rest)) => {
+            let (depth, path) = rest.split_once(':').ok_or_else(invalid)?;
+            let depth = depth.parse().map_err(|_| invalid())?;
+            Ok(Box::new(NeuralPlayer::new(
+                Network::load(path)?,
+                Some(depth),
+            )))
+        }
+        Some(("policy", path)) => Ok(Box::new(PolicyPlayer::new(
+            CompactPolicy::load(path)?,
+            MinmaxPlayer::default(),
+        ))),
+        Some(("book", rest)) => {
+            let (path, engine) = rest.split_once(':').ok_or_else(invalid)?;
+            Ok(Box::new(BookPlayer::new(
+                OpeningBook::load(path)?,
+                player_from_spec(engine)?,
+            )))
+        }
+        Some(("safe", engine)) => Ok(Box::new(Tactical::new(player_from_spec(engine)?))),
+        _ => Err(invalid()),
+    }
+}
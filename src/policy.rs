@@ -0,0 +1,409 @@
+#![allow(dead_code)]
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    json::{FromJson, Json, JsonError, ToJson},
+    rand::Rng,
+};
+
+/// how a bot picks between the columns it has weights for
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SelectionPolicy {
+    /// uniformly among the columns within `exploration` of the best weight
+    #[default]
+    Threshold,
+    /// proportional to `e^(weight / temperature)`, so columns far below the
+    /// best are rarely played. a temperature of 0 always plays the best
+    Softmax { temperature: f64 },
+    /// the best column, or a random one with probability `epsilon`
+    EpsilonGreedy { epsilon: f64 },
+    /// the best `weight + c * sqrt(ln(visits) / column visits)`, trying every
+    /// column of a board at least once
+    Ucb { c: f64 },
+}
+
+impl SelectionPolicy {
+    /// picks one of `choices`, given as `(column, weight)`. `visits` are how
+    /// often each column of the board was picked before, indexed by column
+    pub fn select(
+        &self,
+        choices: &[(usize, i16)],
+        visits: &[u32],
+        exploration: i16,
+        rand: &mut impl Rng,
+    ) -> usize {
+        let max_weight = choices
+            .iter()
+            .map(|&(_, weight)| weight)
+            .max()
+            .expect("game is not tied");
+        let best: Vec<_> = choices
+            .iter()
+            .filter(|&&(_, weight)| weight == max_weight)
+            .map(|&(column, _)| column)
+            .collect();
+
+        match *self {
+            SelectionPolicy::Threshold => {
+                let threshold = max_weight.checked_sub(exploration).unwrap_or(i16::MIN);
+                let within: Vec<_> = choices
+                    .iter()
+                    .filter(|&&(_, weight)| weight >= threshold)
+                    .map(|&(column, _)| column)
+                    .collect();
+                within[rand.below(within.len())]
+            }
+            SelectionPolicy::Softmax { temperature } if temperature <= 0.0 => {
+                best[rand.below(best.len())]
+            }
+            SelectionPolicy::Softmax { temperature } => {
+                // relative to the best weight, so the exponent never overflows
+                let odds: Vec<_> = choices
+                    .iter()
+                    .map(|&(_, weight)| ((weight as f64 - max_weight as f64) / temperature).exp())
+                    .collect();
+                let mut pick = rand.next_f64() * odds.iter().sum::<f64>();
+                for (&(column, _), odds) in choices.iter().zip(odds) {
+                    if pick < odds {
+                        return column;
+                    }
+                    pick -= odds;
+                }
+                choices[choices.len() - 1].0
+            }
+            SelectionPolicy::EpsilonGreedy { epsilon } => {
+                if rand.next_f64() < epsilon {
+                    choices[rand.below(choices.len())].0
+                } else {
+                    best[rand.below(best.len())]
+                }
+            }
+            SelectionPolicy::Ucb { c } => {
+                let unvisited: Vec<_> = choices
+                    .iter()
+                    .filter(|&&(column, _)| visits[column] == 0)
+                    .map(|&(column, _)| column)
+                    .collect();
+                if !unvisited.is_empty() {
+                    return unvisited[rand.below(unvisited.len())];
+                }
+                let total: u32 = choices.iter().map(|&(column, _)| visits[column]).sum();
+                let bound = |&(column, weight): &(usize, i16)| {
+                    weight as f64 + c * ((total as f64).ln() / visits[column] as f64).sqrt()
+                };
+                choices
+                    .iter()
+                    .max_by(|left, right| bound(left).total_cmp(&bound(right)))
+                    .expect("game is not tied")
+                    .0
+            }
+        }
+    }
+
+    /// the knob `Schedule`s turn: temperature, epsilon or c
+    pub fn parameter(&self) -> Option<f64> {
+        match *self {
+            SelectionPolicy::Threshold => None,
+            SelectionPolicy::Softmax { temperature } => Some(temperature),
+            SelectionPolicy::EpsilonGreedy { epsilon } => Some(epsilon),
+            SelectionPolicy::Ucb { c } => Some(c),
+        }
+    }
+
+    pub fn set_parameter(&mut self, value: f64) {
+        match self {
+            SelectionPolicy::Threshold => (),
+            SelectionPolicy::Softmax { temperature } => *temperature = value,
+            SelectionPolicy::EpsilonGreedy { epsilon } => *epsilon = value,
+            SelectionPolicy::Ucb { c } => *c = value,
+        }
+    }
+}
+
+impl Display for SelectionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SelectionPolicy::Threshold => write!(f, "threshold"),
+            SelectionPolicy::Softmax { temperature } => write!(f, "softmax:{temperature}"),
+            SelectionPolicy::EpsilonGreedy { epsilon } => write!(f, "epsilon:{epsilon}"),
+            SelectionPolicy::Ucb { c } => write!(f, "ucb:{c}"),
+        }
+    }
+}
+
+/// `threshold`, `softmax:<temperature>`, `epsilon:<epsilon>` or `ucb:<c>`
+impl FromStr for SelectionPolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("unknown policy '{policy}'");
+        let (name, value) = match policy.split_once(':') {
+            Some((name, value)) => (name, Some(value.parse::<f64>().map_err(|_| invalid())?)),
+            None => (policy, None),
+        };
+        match (name, value) {
+            ("threshold", None) => Ok(SelectionPolicy::Threshold),
+            ("softmax", Some(temperature)) => Ok(SelectionPolicy::Softmax { temperature }),
+            ("epsilon", Some(epsilon)) if (0.0..=1.0).contains(&epsilon) => {
+                Ok(SelectionPolicy::EpsilonGreedy { epsilon })
+            }
+            ("ucb", Some(c)) => Ok(SelectionPolicy::Ucb { c }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// how a value like a temperature changes over the iterations of training
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Schedule {
+    Constant(f64),
+    /// from `start` to `end` over `iterations`, then stays at `end`
+    Linear {
+        start: f64,
+        end: f64,
+        iterations: usize,
+    },
+    /// `start * decay^iteration`, but never below `min`
+    Exponential {
+        start: f64,
+        decay: f64,
+        min: f64,
+    },
+    /// `start`, multiplied by `factor` every `every` iterations
+    Step {
+        start: f64,
+        factor: f64,
+        every: usize,
+    },
+}
+
+impl Schedule {
+    pub fn value_at(&self, iteration: usize) -> f64 {
+        match *self {
+            Schedule::Constant(value) => value,
+            Schedule::Linear {
+                start,
+                end,
+                iterations,
+            } => {
+                if iteration >= iterations {
+                    return end;
+                }
+                start + (end - start) * iteration as f64 / iterations as f64
+            }
+            Schedule::Exponential { start, decay, min } => {
+                (start * decay.powf(iteration as f64)).max(min)
+            }
+            Schedule::Step {
+                start,
+                factor,
+                every,
+            } => start * factor.powi((iteration / every.max(1)) as i32),
+        }
+    }
+}
+
+impl ToJson for SelectionPolicy {
+    fn to_json(&self) -> Json {
+        Json::from(self.to_string())
+    }
+}
+
+impl FromJson for SelectionPolicy {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        json.as_str()
+            .ok_or_else(|| JsonError::InvalidValue(format!("invalid policy '{json}'")))?
+            .parse()
+            .map_err(JsonError::InvalidValue)
+    }
+}
+
+/// a constant is just the number, the rest are objects with a `kind`
+impl ToJson for Schedule {
+    fn to_json(&self) -> Json {
+        match *self {
+            Schedule::Constant(value) => Json::from(value),
+            Schedule::Linear {
+                start,
+                end,
+                iterations,
+            } => Json::object([
+                ("kind", Json::from("linear")),
+                ("start", Json::from(start)),
+                ("end", Json::from(end)),
+                ("iterations", Json::from(iterations)),
+            ]),
+            Schedule::Exponential { start, decay, min } => Json::object([
+                ("kind", Json::from("exponential")),
+                ("start", Json::from(start)),
+                ("decay", Json::from(decay)),
+                ("min", Json::from(min)),
+            ]),
+            Schedule::Step {
+                start,
+                factor,
+                every,
+            } => Json::object([
+                ("kind", Json::from("step")),
+                ("start", Json::from(start)),
+                ("factor", Json::from(factor)),
+                ("every", Json::from(every)),
+            ]),
+        }
+    }
+}
+
+impl FromJson for Schedule {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        if let Some(value) = json.as_f64() {
+            return Ok(Schedule::Constant(value));
+        }
+        match json.field_as("kind", Json::as_str)? {
+            "linear" => Ok(Schedule::Linear {
+                start: json.field_as("start", Json::as_f64)?,
+                end: json.field_as("end", Json::as_f64)?,
+                iterations: json.field_as("iterations", Json::as_usize)?,
+            }),
+            "exponential" => Ok(Schedule::Exponential {
+                start: json.field_as("start", Json::as_f64)?,
+                decay: json.field_as("decay", Json::as_f64)?,
+                min: json.field_or("min", 0.0, Json::as_f64)?,
+            }),
+            "step" => Ok(Schedule::Step {
+                start: json.field_as("start", Json::as_f64)?,
+                factor: json.field_as("factor", Json::as_f64)?,
+                every: json.field_as("every", Json::as_usize)?,
+            }),
+            kind => Err(JsonError::InvalidValue(format!(
+                "unknown schedule '{kind}'"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        json::{FromJson, ToJson},
+        rand::Rand,
+    };
+
+    use super::{Schedule, SelectionPolicy};
+
+    fn counts(policy: SelectionPolicy, choices: &[(usize, i16)]) -> [u32; 7] {
+        let mut rand = Rand::new(0x80085);
+        let mut counts = [0; 7];
+        for _ in 0..10_000 {
+            counts[policy.select(choices, &counts, 50, &mut rand)] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn threshold_ignores_the_gap() {
+        // 1 and 49 below the best are both within `exploration`
+        let counts = counts(SelectionPolicy::Threshold, &[(0, 100), (1, 99), (2, 51)]);
+        assert!(counts[1].abs_diff(counts[2]) < 500);
+    }
+
+    #[test]
+    fn softmax_prefers_close_weights() {
+        let choices = [(0, 100), (1, 99), (2, 51), (6, -30000)];
+        let counts = counts(SelectionPolicy::Softmax { temperature: 10.0 }, &choices);
+        assert!(counts[1] > 4_000);
+        assert!(counts[2] < 100);
+        assert_eq!(counts[6], 0);
+    }
+
+    #[test]
+    fn zero_temperature_is_greedy() {
+        let choices = [(0, 100), (1, 99)];
+        let counts = counts(SelectionPolicy::Softmax { temperature: 0.0 }, &choices);
+        assert_eq!(counts[0], 10_000);
+    }
+
+    #[test]
+    fn epsilon_greedy() {
+        let choices = [(0, 100), (3, 0)];
+        let counts = counts(SelectionPolicy::EpsilonGreedy { epsilon: 0.2 }, &choices);
+        assert!((800..1200).contains(&counts[3]));
+    }
+
+    #[test]
+    fn ucb_visits_everything() {
+        let choices = [(0, 100), (1, 0), (2, -100)];
+        let counts = counts(SelectionPolicy::Ucb { c: 100.0 }, &choices);
+        assert!(counts[0] > counts[1] && counts[1] > counts[2]);
+        assert!(counts[2] > 0);
+    }
+
+    #[test]
+    fn parse() {
+        for policy in ["threshold", "softmax:2.5", "epsilon:0.1", "ucb:40"] {
+            assert_eq!(
+                policy.parse::<SelectionPolicy>().unwrap().to_string(),
+                policy
+            );
+        }
+        assert!("epsilon:2".parse::<SelectionPolicy>().is_err());
+        assert!("softmax".parse::<SelectionPolicy>().is_err());
+    }
+
+    #[test]
+    fn schedules() {
+        let linear = Schedule::Linear {
+            start: 50.0,
+            end: 5.0,
+            iterations: 10,
+        };
+        assert_eq!(linear.value_at(0), 50.0);
+        assert_eq!(linear.value_at(5), 27.5);
+        assert_eq!(linear.value_at(100), 5.0);
+        let exponential = Schedule::Exponential {
+            start: 1.0,
+            decay: 0.5,
+            min: 0.1,
+        };
+        assert_eq!(exponential.value_at(2), 0.25);
+        assert_eq!(exponential.value_at(10), 0.1);
+        let step = Schedule::Step {
+            start: 8.0,
+            factor: 0.5,
+            every: 3,
+        };
+        assert_eq!(step.value_at(2), 8.0);
+        assert_eq!(step.value_at(6), 2.0);
+        assert_eq!(Schedule::Constant(3.0).value_at(7), 3.0);
+    }
+
+    #[test]
+    fn json() {
+        let schedules = [
+            Schedule::Constant(3.0),
+            Schedule::Linear {
+                start: 50.0,
+                end: 5.0,
+                iterations: 10,
+            },
+            Schedule::Exponential {
+                start: 1.0,
+                decay: 0.5,
+                min: 0.1,
+            },
+            Schedule::Step {
+                start: 8.0,
+                factor: 0.5,
+                every: 3,
+            },
+        ];
+        for schedule in schedules {
+            assert_eq!(Schedule::from_json(&schedule.to_json()).unwrap(), schedule);
+        }
+        assert!(Schedule::from_json_str(r#"{"kind": "cosine"}"#).is_err());
+        let policy = SelectionPolicy::Softmax { temperature: 2.5 };
+        assert_eq!(
+            SelectionPolicy::from_json(&policy.to_json()).unwrap(),
+            policy
+        );
+    }
+}
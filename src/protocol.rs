@@ -0,0 +1,269 @@
+#![allow(dead_code)]
+use std::{
+    io::{self, BufRead, Write},
+    time::Duration,
+};
+
+use crate::{
+    board::{Board, Chip, MovesError},
+    player::{player_from_spec, Player, SearchLimits},
+};
+
+/// a line based protocol for driving an engine over stdin/stdout, modeled
+/// after uci. the columns of a move list are written as digits, i.e.
+/// `position moves 4453`
+///
+/// ```text
+/// > uci
+/// < id name connect-4-ai
+/// < option name Engine type string default minmax
+/// < uciok
+/// > position moves 4453
+/// > go depth 3
+/// < info depth 0 score 16 nodes 7 time 0
+/// < ...
+/// < bestmove 3
+/// ```
+pub struct Protocol {
+    engine: Box<dyn Player>,
+    engine_spec: String,
+    board: Board,
+    game_over: bool,
+}
+
+impl Protocol {
+    pub const NAME: &'static str = "connect-4-ai";
+
+    pub fn new(engine_spec: &str) -> io::Result<Self> {
+        Ok(Self {
+            engine: player_from_spec(engine_spec)?,
+            engine_spec: engine_spec.to_string(),
+            board: Board::new(),
+            game_over: false,
+        })
+    }
+
+    /// answers commands from `input` until `quit` or the end of input
+    pub fn run(mut self, input: impl BufRead, output: &mut impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            if !self.handle(&line?, output)? {
+                break;
+            }
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// answers a single command, `false` once the engine should quit
+    pub fn handle(&mut self, line: &str, output: &mut impl Write) -> io::Result<bool> {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            None => (),
+            Some("uci") => {
+                writeln!(output, "id name {}", Self::NAME)?;
+                writeln!(
+                    output,
+                    "option name Engine type string default {}",
+                    self.engine_spec
+                )?;
+                writeln!(
+                    output,
+                    "option name Depth type spin default {} min 0 max 42",
+                    Board::MINMAX_DEPTH
+                )?;
+                writeln!(
+                    output,
+                    "option name Evaluator type string default opportunities"
+                )?;
+                writeln!(output, "option name Exploration type spin default 5")?;
+                writeln!(output, "option name Seed type spin default 0")?;
+                writeln!(output, "option name Policy type string default threshold")?;
+                writeln!(output, "uciok")?;
+            }
+            Some("isready") => writeln!(output, "readyok")?,
+            Some("ucinewgame") | Some("newgame") => {
+                self.board = Board::new();
+                self.game_over = false;
+            }
+            Some("position") => {
+                let words: Vec<_> = words.collect();
+                if let Err(msg) = self.set_position(&words) {
+                    writeln!(output, "info string {msg}")?;
+                }
+            }
+            Some("go") => {
+                let words: Vec<_> = words.collect();
+                match Self::parse_limits(&words) {
+                    Ok(limits) => self.go(limits, output)?,
+                    Err(msg) => writeln!(output, "info string {msg}")?,
+                }
+            }
+            Some("stop") => (),
+            Some("setoption") => {
+                let words: Vec<_> = words.collect();
+                if let Err(msg) = self.set_option(&words) {
+                    writeln!(output, "info string {msg}")?;
+                }
+            }
+            Some("d") | Some("display") => writeln!(output, "{}", self.board)?,
+            Some("quit") => return Ok(false),
+            Some(command) => writeln!(output, "info string unknown command '{command}'")?,
+        }
+        Ok(true)
+    }
+
+    /// `[startpos] [moves <columns>]`
+    fn set_position(&mut self, words: &[&str]) -> Result<(), String> {
+        let words = match words {
+            ["startpos", rest @ ..] => rest,
+            words => words,
+        };
+        let moves = match words {
+            [] => String::new(),
+            ["moves", moves @ ..] => moves.concat(),
+            _ => return Err(format!("invalid position '{}'", words.join(" "))),
+        };
+
+        let columns = moves
+            .chars()
+            .map(|column| {
+                column
+                    .to_digit(10)
+                    .map(|column| column as usize)
+                    .ok_or_else(|| format!("invalid column '{column}'"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let board = Board::from_columns(columns).map_err(|err| match err {
+            MovesError::GameOver => format!("moves after the game ended in '{moves}'"),
+            MovesError::ColumnOccupied | MovesError::InvalidColumn => {
+                format!("illegal move in '{moves}'")
+            }
+        })?;
+        self.board = board;
+        self.game_over = board.is_over();
+        Ok(())
+    }
+
+    /// `[depth <plies>] [movetime <milliseconds>]`
+    fn parse_limits(words: &[&str]) -> Result<SearchLimits, String> {
+        let mut limits = SearchLimits::default();
+        let mut words = words.iter();
+        while let Some(&word) = words.next() {
+            let value = words
+                .next()
+                .ok_or_else(|| format!("missing value for '{word}'"))?;
+            let invalid = || format!("invalid value '{value}' for '{word}'");
+            match word {
+                "depth" => limits.depth = Some(value.parse().map_err(|_| invalid())?),
+                "movetime" => {
+                    let millis = value.parse().map_err(|_| invalid())?;
+                    limits.movetime = Some(Duration::from_millis(millis));
+                }
+                _ => return Err(format!("unknown limit '{word}'")),
+            }
+        }
+        Ok(limits)
+    }
+
+    fn go(&mut self, limits: SearchLimits, output: &mut impl Write) -> io::Result<()> {
+        if self.game_over {
+            writeln!(output, "bestmove none")?;
+            return Ok(());
+        }
+        let turn: Chip = self.board.turn();
+        let mut reported = Ok(false);
+        let (column, score) = self.engine.search(self.board, turn, limits, &mut |info| {
+            if reported.is_ok() {
+                reported = writeln!(
+                    output,
+                    "info depth {} score {} nodes {} time {}",
+                    info.depth,
+                    info.score,
+                    info.nodes,
+                    info.elapsed.as_millis()
+                )
+                .and_then(|_| output.flush())
+                .map(|_| true);
+            }
+        });
+        if !reported? {
+            if let Some(score) = score {
+                writeln!(output, "info score {score}")?;
+            }
+        }
+        writeln!(output, "bestmove {column}")
+    }
+
+    /// `name <name> value <value>`
+    fn set_option(&mut self, words: &[&str]) -> Result<(), String> {
+        let ["name", name, "value", value @ ..] = words else {
+            return Err(format!("invalid option '{}'", words.join(" ")));
+        };
+        let name = name.to_lowercase();
+        let value = value.join(" ");
+        if name == "engine" {
+            self.engine = player_from_spec(&value).map_err(|err| err.to_string())?;
+            self.engine_spec = value;
+            return Ok(());
+        }
+        if !self.engine.set_option(&name, &value) {
+            return Err(format!(
+                "engine '{}' does not accept '{value}' for '{name}'",
+                self.engine_spec
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Protocol;
+
+    fn run(commands: &str) -> String {
+        let mut output = Vec::new();
+        Protocol::new("minmax:1")
+            .unwrap()
+            .run(commands.as_bytes(), &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn takes_the_win() {
+        let output = run("position moves 010101\ngo depth 2\nquit\n");
+        assert!(output.contains("info depth 0 score 1000"));
+        assert!(output.ends_with("bestmove 0\n"));
+    }
+
+    #[test]
+    fn rejects_illegal_positions() {
+        let output = run("position moves 0000000\nposition moves 01010101\ngo\n");
+        assert!(output.contains("info string illegal move in '0000000'"));
+        assert!(output.contains("info string moves after the game ended in '01010101'"));
+        assert!(output.lines().last().unwrap().starts_with("bestmove "));
+    }
+
+    #[test]
+    fn movetime_without_depth() {
+        // deepens past the engine's own depth until the time is up
+        let output = run("go movetime 200\nquit\n");
+        assert!(output.contains("info depth 2 "));
+        assert!(output.lines().last().unwrap().starts_with("bestmove "));
+    }
+
+    #[test]
+    fn game_over() {
+        let output = run("position moves 0101010\ngo\n");
+        assert_eq!(output, "bestmove none\n");
+    }
+
+    #[test]
+    fn options() {
+        let output = run("setoption name Depth value 3\nsetoption name Seed value 3\n");
+        assert_eq!(
+            output,
+            "info string engine 'minmax:1' does not accept '3' for 'seed'\n"
+        );
+    }
+}
@@ -0,0 +1,218 @@
+#![allow(dead_code)]
+use std::ops::Range;
+
+use crate::json::{FromJson, Json, JsonError, ToJson};
+
+/// a source of random numbers, anything built on top of `next_u64` comes for free
+pub trait Rng {
+    fn next_u64(&mut self) -> u64;
+
+    /// uniform in `0..bound`, without the bias `next_u64() % bound` has.
+    /// https://arxiv.org/abs/1805.10941
+    fn below(&mut self, bound: usize) -> usize {
+        assert!(bound > 0, "cannot sample from an empty range");
+        let bound = bound as u64;
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let product = self.next_u64() as u128 * bound as u128;
+            if product as u64 >= threshold {
+                break (product >> 64) as usize;
+            }
+        }
+    }
+
+    fn range(&mut self, range: Range<usize>) -> usize {
+        range.start + self.below(range.len())
+    }
+
+    /// uniform in `0.0..1.0`
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    fn choose<'items, T>(&mut self, items: &'items [T]) -> Option<&'items T>
+    where
+        Self: Sized,
+    {
+        if items.is_empty() {
+            return None;
+        }
+        Some(&items[self.below(items.len())])
+    }
+}
+
+/// https://prng.di.unimi.it/splitmix64.c, used to spread a seed over a bigger state
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// xoshiro256**, https://prng.di.unimi.it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rand([u64; 4]);
+
+impl Rand {
+    const JUMP: [u64; 4] = [
+        0x180ec6d33cfd0aba,
+        0xd5a61266f0c9392c,
+        0xa9582618e03fc9aa,
+        0x39abdc4529b1661c,
+    ];
+
+    pub fn new(seed: usize) -> Self {
+        let mut seed = seed as u64;
+        Self(std::array::from_fn(|_| splitmix64(&mut seed)))
+    }
+
+    /// the `index`th of many independent streams from the same seed, i.e. one
+    /// per worker thread
+    pub fn stream(seed: usize, index: usize) -> Self {
+        let mut rand = Self::new(seed);
+        for _ in 0..index {
+            rand.jump();
+        }
+        rand
+    }
+
+    /// skips ahead 2^128 numbers, so the numbers before and after never overlap
+    pub fn jump(&mut self) {
+        let mut state = [0; 4];
+        for jump in Self::JUMP {
+            for bit in 0..64 {
+                if jump & (1 << bit) != 0 {
+                    for (state, current) in state.iter_mut().zip(self.0) {
+                        *state ^= current;
+                    }
+                }
+                self.next_u64();
+            }
+        }
+        self.0 = state;
+    }
+
+    /// a generator independent of this one, i.e. for a new gladiator. this one
+    /// jumps ahead to make room for it
+    pub fn split(&mut self) -> Self {
+        let split = self.clone();
+        self.jump();
+        split
+    }
+
+    pub fn state(&self) -> [u64; 4] {
+        self.0
+    }
+
+    /// `None` for the all zero state, which would only ever produce zeros
+    pub fn from_state(state: [u64; 4]) -> Option<Self> {
+        if state == [0; 4] {
+            return None;
+        }
+        Some(Self(state))
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for (chunk, word) in bytes.chunks_exact_mut(8).zip(self.0) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; 32]) -> Option<Self> {
+        Self::from_state(std::array::from_fn(|idx| {
+            u64::from_le_bytes(
+                bytes[idx * 8..idx * 8 + 8]
+                    .try_into()
+                    .expect("8 bytes per word"),
+            )
+        }))
+    }
+}
+
+impl Rng for Rand {
+    fn next_u64(&mut self) -> u64 {
+        let state = &mut self.0;
+        let result = state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = state[1] << 17;
+        state[2] ^= state[0];
+        state[3] ^= state[1];
+        state[1] ^= state[2];
+        state[0] ^= state[3];
+        state[2] ^= t;
+        state[3] = state[3].rotate_left(45);
+        result
+    }
+}
+
+/// the state as a hex string, json numbers cannot hold a whole `u64`
+impl ToJson for Rand {
+    fn to_json(&self) -> Json {
+        let hex: String = self.0.iter().map(|word| format!("{word:016x}")).collect();
+        Json::from(hex)
+    }
+}
+
+impl FromJson for Rand {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        let invalid = || JsonError::InvalidValue(format!("invalid rand state '{json}'"));
+        let hex = json
+            .as_str()
+            .filter(|hex| hex.len() == 64)
+            .ok_or_else(invalid)?;
+        let mut state = [0; 4];
+        for (idx, word) in state.iter_mut().enumerate() {
+            let digits = hex.get(idx * 16..idx * 16 + 16).ok_or_else(invalid)?;
+            *word = u64::from_str_radix(digits, 16).map_err(|_| invalid())?;
+        }
+        Self::from_state(state).ok_or_else(invalid)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::json::{FromJson, ToJson};
+
+    use super::{Rand, Rng};
+
+    #[test]
+    fn reference_output() {
+        let mut rand = Rand::from_state([1, 2, 3, 4]).unwrap();
+        let numbers: Vec<_> = (0..4).map(|_| rand.next_u64()).collect();
+        assert_eq!(numbers, [11520, 0, 1509978240, 1215971899390074240]);
+        assert_eq!(Rand::from_state([0; 4]), None);
+    }
+
+    #[test]
+    fn below_is_uniform() {
+        let mut rng = Rand::new(0x80085);
+        let mut counts = [0; 7];
+        for _ in 0..70_000 {
+            counts[rng.below(7)] += 1;
+        }
+        assert!(counts.iter().all(|&count| (9_500..10_500).contains(&count)));
+        assert!((0..1000).all(|_| (3..5).contains(&rng.range(3..5))));
+    }
+
+    #[test]
+    fn state_round_trip() {
+        let mut rand = Rand::new(42);
+        rand.next_u64();
+        assert_eq!(Rand::from_bytes(&rand.to_bytes()), Some(rand.clone()));
+        assert_eq!(Rand::from_json(&rand.to_json()).unwrap(), rand);
+    }
+
+    #[test]
+    fn streams() {
+        let mut rand = Rand::new(7);
+        let first = rand.split();
+        let second = rand.split();
+        assert_eq!(first, Rand::stream(7, 0));
+        assert_eq!(second, Rand::stream(7, 1));
+        assert_eq!(rand, Rand::stream(7, 2));
+        let (mut first, mut second) = (first, second);
+        assert!((0..100).all(|_| first.next_u64() != second.next_u64()));
+    }
+}
@@ -0,0 +1,258 @@
+#![allow(dead_code)]
+use std::{fs, io, path::Path};
+
+use crate::{
+    board::Chip,
+    bot::Action,
+    json::{FromJson, Json, JsonError, ToJson},
+};
+
+/// how much harder a lesson is for later moves, `scale * turn^exponent`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeverityCurve {
+    pub scale: f64,
+    pub exponent: f64,
+}
+
+impl SeverityCurve {
+    pub fn at(&self, turn: usize) -> i16 {
+        (self.scale * (turn as f64).powf(self.exponent)) as i16
+    }
+}
+
+impl Default for SeverityCurve {
+    fn default() -> Self {
+        Self {
+            scale: 0.02,
+            exponent: 2.0,
+        }
+    }
+}
+
+/// what each result is worth to one side, negative values punish
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SideRewards {
+    pub win: i32,
+    pub loss: i32,
+    pub tie: i32,
+}
+
+/// how trainers reward and punish the moves of their bots once a game is over
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RewardConfig {
+    pub red: SideRewards,
+    pub yellow: SideRewards,
+    /// the reward for a move is multiplied by this once for every ply it was
+    /// played before the end of the game
+    pub discount: f64,
+    /// added on top of the reward, growing with the turn the move was played in
+    pub severity: SeverityCurve,
+    /// whether the last move of a game is set straight to the highest weight
+    /// for a reward or the lowest for a punishment, instead of learning like
+    /// the others
+    pub decisive_last_move: bool,
+}
+
+impl Default for RewardConfig {
+    /// what trainers always used: 10 for a win, and a tie is a small loss for
+    /// red, who had the advantage of moving first
+    fn default() -> Self {
+        Self {
+            red: SideRewards {
+                win: 10,
+                loss: -10,
+                tie: -1,
+            },
+            yellow: SideRewards {
+                win: 10,
+                loss: -10,
+                tie: 1,
+            },
+            discount: 1.0,
+            severity: SeverityCurve::default(),
+            decisive_last_move: true,
+        }
+    }
+}
+
+impl RewardConfig {
+    pub fn side(&self, chip: Chip) -> &SideRewards {
+        match chip {
+            Chip::Red => &self.red,
+            Chip::Yellow => &self.yellow,
+        }
+    }
+
+    /// what the game ending with `winner` is worth to `chip`
+    pub fn value(&self, chip: Chip, winner: Option<Chip>) -> i32 {
+        let side = self.side(chip);
+        match winner {
+            Some(winner) if winner == chip => side.win,
+            Some(_) => side.loss,
+            None => side.tie,
+        }
+    }
+
+    pub fn action(&self, chip: Chip, winner: Option<Chip>) -> Action {
+        let value = self.value(chip, winner);
+        if value < 0 {
+            Action::Punish(value.unsigned_abs())
+        } else {
+            Action::Reward(value as u32)
+        }
+    }
+
+    /// the weight the last move of a game gets for `action`, if it doesn't
+    /// learn like the others. a result worth 0 leaves it alone
+    pub fn final_weight(&self, action: &Action) -> Option<i16> {
+        match action {
+            _ if !self.decisive_last_move => None,
+            Action::Reward(0) | Action::Punish(0) => None,
+            Action::Reward(_) => Some(i16::MAX),
+            Action::Punish(_) => Some(i16::MIN),
+        }
+    }
+
+    /// `base` for a move played `plies_before_end` plies before the game ended
+    pub fn discounted(&self, base: u32, plies_before_end: usize) -> i16 {
+        let discounted = base as f64 * self.discount.powi(plies_before_end as i32);
+        discounted.round().clamp(0.0, i16::MAX as f64) as i16
+    }
+
+    /// a json file like the one `to_json` writes, where any missing field
+    /// keeps its default
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::from_json_str(&text)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+    }
+}
+
+impl ToJson for SideRewards {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("win", Json::from(self.win)),
+            ("loss", Json::from(self.loss)),
+            ("tie", Json::from(self.tie)),
+        ])
+    }
+}
+
+impl ToJson for RewardConfig {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("red", self.red.to_json()),
+            ("yellow", self.yellow.to_json()),
+            ("discount", Json::from(self.discount)),
+            (
+                "severity",
+                Json::object([
+                    ("scale", Json::from(self.severity.scale)),
+                    ("exponent", Json::from(self.severity.exponent)),
+                ]),
+            ),
+            ("decisive_last_move", Json::from(self.decisive_last_move)),
+        ])
+    }
+}
+
+fn side_from_json(json: &Json, default: SideRewards) -> Result<SideRewards, JsonError> {
+    let int = |json: &Json| json.as_i64()?.try_into().ok();
+    Ok(SideRewards {
+        win: json.field_or("win", default.win, int)?,
+        loss: json.field_or("loss", default.loss, int)?,
+        tie: json.field_or("tie", default.tie, int)?,
+    })
+}
+
+impl FromJson for RewardConfig {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        let default = Self::default();
+        if !matches!(json, Json::Object(_)) {
+            return Err(JsonError::InvalidValue(
+                "expected an object of rewards".to_string(),
+            ));
+        }
+        let severity = json.get("severity").unwrap_or(&Json::Null);
+        Ok(Self {
+            red: match json.get("red") {
+                Some(red) => side_from_json(red, default.red)?,
+                None => default.red,
+            },
+            yellow: match json.get("yellow") {
+                Some(yellow) => side_from_json(yellow, default.yellow)?,
+                None => default.yellow,
+            },
+            discount: json.field_or("discount", default.discount, Json::as_f64)?,
+            severity: SeverityCurve {
+                scale: severity.field_or("scale", default.severity.scale, Json::as_f64)?,
+                exponent: severity.field_or("exponent", default.severity.exponent, Json::as_f64)?,
+            },
+            decisive_last_move: json.field_or(
+                "decisive_last_move",
+                default.decisive_last_move,
+                Json::as_bool,
+            )?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        board::Chip,
+        json::{FromJson, ToJson},
+    };
+
+    use super::RewardConfig;
+
+    #[test]
+    fn defaults_match_the_old_literals() {
+        let rewards = RewardConfig::default();
+        assert_eq!(rewards.value(Chip::Red, Some(Chip::Red)), 10);
+        assert_eq!(rewards.value(Chip::Yellow, Some(Chip::Red)), -10);
+        assert_eq!(rewards.value(Chip::Red, None), -1);
+        assert_eq!(rewards.value(Chip::Yellow, None), 1);
+        assert_eq!(rewards.discounted(10, 20), 10);
+        assert_eq!(rewards.severity.at(10), 2);
+    }
+
+    #[test]
+    fn json() {
+        let rewards = RewardConfig {
+            discount: 0.5,
+            ..RewardConfig::default()
+        };
+        assert_eq!(
+            RewardConfig::from_json(&rewards.to_json()).unwrap(),
+            rewards
+        );
+        assert_eq!(rewards.discounted(10, 2), 3);
+
+        let partial =
+            RewardConfig::from_json_str(r#"{"yellow": {"tie": 0}, "severity": {"scale": 0}}"#)
+                .unwrap();
+        assert_eq!(partial.yellow.tie, 0);
+        assert_eq!(partial.yellow.win, 10);
+        assert_eq!(partial.severity.scale, 0.0);
+        assert_eq!(partial.severity.exponent, 2.0);
+        assert!(RewardConfig::from_json_str(r#"{"red": {"win": "a lot"}}"#).is_err());
+    }
+
+    #[test]
+    fn final_weight() {
+        let rewards = RewardConfig::from_json_str(r#"{"yellow": {"tie": 0}}"#).unwrap();
+        let final_weight = |chip, winner| rewards.final_weight(&rewards.action(chip, winner));
+        assert_eq!(final_weight(Chip::Red, Some(Chip::Red)), Some(i16::MAX));
+        assert_eq!(final_weight(Chip::Yellow, Some(Chip::Red)), Some(i16::MIN));
+        assert_eq!(final_weight(Chip::Red, None), Some(i16::MIN));
+        // a neutral result is nothing to learn from
+        assert_eq!(final_weight(Chip::Yellow, None), None);
+
+        let gradual = RewardConfig::from_json_str(r#"{"decisive_last_move": false}"#).unwrap();
+        assert_eq!(
+            gradual.final_weight(&gradual.action(Chip::Red, Some(Chip::Red))),
+            None
+        );
+    }
+}
@@ -0,0 +1,429 @@
+#![allow(dead_code)]
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::{
+    board::{Chip, PlaceChipError},
+    bot::Game,
+    json::{Json, ToJson},
+    player::Player,
+};
+
+struct ServerGame {
+    game: Game,
+    moves: Vec<usize>,
+    winner: Option<Chip>,
+    engine: Box<dyn Player>,
+}
+
+impl ServerGame {
+    fn over(&self) -> bool {
+        self.winner.is_some() || self.game.board.filled()
+    }
+
+    fn place_chip(&mut self, column: usize) -> Result<(), PlaceChipError> {
+        let row = self.game.board.place_chip(column, self.game.turn)?;
+        self.moves.push(column);
+        if self.game.board.winner(column, row).is_some() {
+            self.winner = Some(self.game.turn);
+        }
+        self.game.next_turn();
+        Ok(())
+    }
+
+    fn to_json(&self, id: u64) -> Json {
+        let status = match self.winner {
+            Some(Chip::Red) => "red_won",
+            Some(Chip::Yellow) => "yellow_won",
+            None if self.game.board.filled() => "tie",
+            None => "in_progress",
+        };
+        Json::object([
+            ("id", Json::from(id)),
+            ("status", Json::from(status)),
+            ("turn", self.game.turn.to_json()),
+            ("moves", Json::from(self.moves.clone())),
+            ("board", self.game.board.to_json()),
+        ])
+    }
+}
+
+/// an http response with a json body
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Json,
+}
+
+impl Response {
+    fn ok(body: Json) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: Json::object([("error", Json::from(message.into()))]),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            201 => "Created",
+            204 => "No Content",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            409 => "Conflict",
+            413 => "Payload Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// hosts games over http for local front-ends
+///
+/// - `POST /games` with an optional `{"depth": 4}` creates a game
+/// - `GET /games/<id>` returns the state of a game
+/// - `POST /games/<id>/moves` with `{"column": 3}` places a chip for whoever's turn it is
+/// - `POST /games/<id>/engine-move` lets the engine place a chip
+/// - `DELETE /games/<id>` forgets a game
+pub struct Server {
+    games: HashMap<u64, ServerGame>,
+    next_id: u64,
+    new_engine: Box<dyn Fn() -> io::Result<Box<dyn Player>>>,
+    read_timeout: Duration,
+}
+
+impl Server {
+    const MAX_BODY_LEN: usize = 64 * 1024;
+
+    /// `new_engine` is called once per game, to get the opponent for it
+    pub fn new(new_engine: impl Fn() -> io::Result<Box<dyn Player>> + 'static) -> Self {
+        Self {
+            games: HashMap::new(),
+            next_id: 1,
+            new_engine: Box::new(new_engine),
+            read_timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// how long a client may take to send its request. requests are served
+    /// one at a time, so an idle client holds up everyone else until then
+    pub fn with_read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = read_timeout;
+        self
+    }
+
+    pub fn handle(&mut self, method: &str, path: &str, body: &str) -> Response {
+        let body = if body.trim().is_empty() {
+            Json::Object(Vec::new())
+        } else {
+            match Json::parse(body) {
+                Ok(body) => body,
+                Err(err) => return Response::error(400, format!("invalid json: {err}")),
+            }
+        };
+        let segments: Vec<_> = path
+            .split('?')
+            .next()
+            .unwrap_or("")
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+
+        match (method, segments.as_slice()) {
+            ("POST", ["games"]) => self.create_game(&body),
+            (_, ["games"]) => Response::error(405, "method not allowed"),
+            (method, ["games", id, rest @ ..]) => {
+                let Some(id) = id.parse().ok().filter(|id| self.games.contains_key(id)) else {
+                    return Response::error(404, format!("no game '{id}'"));
+                };
+                match (method, rest) {
+                    ("GET", []) => Response::ok(self.games[&id].to_json(id)),
+                    ("DELETE", []) => {
+                        self.games.remove(&id);
+                        Response {
+                            status: 204,
+                            body: Json::Null,
+                        }
+                    }
+                    ("POST", ["moves"]) => self.post_move(id, &body),
+                    ("POST", ["engine-move"]) => self.engine_move(id),
+                    (_, [] | ["moves"] | ["engine-move"]) => {
+                        Response::error(405, "method not allowed")
+                    }
+                    _ => Response::error(404, format!("no route '{path}'")),
+                }
+            }
+            _ => Response::error(404, format!("no route '{path}'")),
+        }
+    }
+
+    fn create_game(&mut self, body: &Json) -> Response {
+        let mut engine = match (self.new_engine)() {
+            Ok(engine) => engine,
+            Err(err) => return Response::error(500, format!("can't start the engine: {err}")),
+        };
+        if let Some(depth) = body.get("depth") {
+            let Some(depth) = depth.as_usize() else {
+                return Response::error(400, "'depth' must be a whole number");
+            };
+            if !engine.set_option("depth", &depth.to_string()) {
+                return Response::error(400, "the engine does not take a depth");
+            }
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let game = ServerGame {
+            game: Game::new(),
+            moves: Vec::new(),
+            winner: None,
+            engine,
+        };
+        let body = game.to_json(id);
+        self.games.insert(id, game);
+        Response { status: 201, body }
+    }
+
+    fn post_move(&mut self, id: u64, body: &Json) -> Response {
+        let game = self.games.get_mut(&id).expect("id was checked by handle");
+        let Some(column) = body.get("column").and_then(Json::as_usize) else {
+            return Response::error(400, "expected a body like {\"column\": 3}");
+        };
+        if game.over() {
+            return Response::error(409, "the game is over");
+        }
+        match game.place_chip(column) {
+            Ok(()) => Response::ok(game.to_json(id)),
+            Err(PlaceChipError::ColumnOccupied) => {
+                Response::error(409, format!("column '{column}' is full"))
+            }
+            Err(PlaceChipError::InvalidColumn) => {
+                Response::error(400, format!("invalid column '{column}'"))
+            }
+        }
+    }
+
+    fn engine_move(&mut self, id: u64) -> Response {
+        let game = self.games.get_mut(&id).expect("id was checked by handle");
+        if game.over() {
+            return Response::error(409, "the game is over");
+        }
+        let (column, score) = game
+            .engine
+            .choose_with_score(game.game.board, game.game.turn);
+        if game.place_chip(column).is_err() {
+            return Response::error(500, format!("the engine chose invalid column '{column}'"));
+        }
+        let mut body = game.to_json(id);
+        if let Json::Object(fields) = &mut body {
+            fields.push(("column".to_string(), Json::from(column)));
+            fields.push(("score".to_string(), Json::from(score)));
+        }
+        Response::ok(body)
+    }
+
+    pub fn serve(self, address: impl ToSocketAddrs) -> io::Result<()> {
+        let listener = TcpListener::bind(address)?;
+        println!("listening on http://{}", listener.local_addr()?);
+        self.serve_on(listener)
+    }
+
+    /// serves one request at a time until the listener fails
+    pub fn serve_on(mut self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            if let Err(err) = self.serve_connection(stream?) {
+                eprintln!("connection failed: {err}");
+            }
+        }
+        Ok(())
+    }
+
+    fn serve_connection(&mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(self.read_timeout))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut request_line = request_line.split_whitespace();
+        let (Some(method), Some(path)) = (request_line.next(), request_line.next()) else {
+            return Self::respond(stream, &Response::error(400, "invalid request line"));
+        };
+
+        let mut content_len = 0;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    content_len = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        if content_len > Self::MAX_BODY_LEN {
+            return Self::respond(stream, &Response::error(413, "body too large"));
+        }
+        let mut body = vec![0; content_len];
+        reader.read_exact(&mut body)?;
+
+        let response = if method == "OPTIONS" {
+            Response {
+                status: 204,
+                body: Json::Null,
+            }
+        } else {
+            self.handle(method, path, &String::from_utf8_lossy(&body))
+        };
+        Self::respond(stream, &response)
+    }
+
+    fn respond(mut stream: TcpStream, response: &Response) -> io::Result<()> {
+        let body = match response.status {
+            204 => String::new(),
+            _ => response.body.to_string(),
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\n\
+             Content-Type: application/json\r\n\
+             Content-Length: {}\r\n\
+             Access-Control-Allow-Origin: *\r\n\
+             Access-Control-Allow-Methods: GET, POST, DELETE, OPTIONS\r\n\
+             Access-Control-Allow-Headers: Content-Type\r\n\
+             Connection: close\r\n\
+             \r\n\
+             {body}",
+            response.status,
+            response.reason(),
+            body.len(),
+        )?;
+        stream.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{self, Read, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+        time::Duration,
+    };
+
+    use crate::{json::Json, player::MinmaxPlayer};
+
+    use super::Server;
+
+    fn server() -> Server {
+        Server::new(|| Ok(Box::new(MinmaxPlayer::new(1))))
+    }
+
+    #[test]
+    fn play_a_game() {
+        let mut server = server();
+        let created = server.handle("POST", "/games", "");
+        assert_eq!(created.status, 201);
+        assert_eq!(created.body.get("id"), Some(&Json::from(1)));
+        assert_eq!(created.body.get("turn"), Some(&Json::from("red")));
+
+        let moved = server.handle("POST", "/games/1/moves", r#"{"column": 3}"#);
+        assert_eq!(moved.status, 200);
+        assert_eq!(moved.body.get("moves"), Some(&Json::from(vec![3])));
+
+        let engine = server.handle("POST", "/games/1/engine-move", "");
+        assert_eq!(engine.status, 200);
+        assert_eq!(engine.body.get("turn"), Some(&Json::from("red")));
+        assert!(engine.body.get("column").and_then(Json::as_usize).is_some());
+
+        let state = server.handle("GET", "/games/1", "");
+        assert_eq!(state.body.get("status"), Some(&Json::from("in_progress")));
+    }
+
+    #[test]
+    fn rejects_invalid_requests() {
+        let mut server = server();
+        server.handle("POST", "/games", r#"{"depth": 2}"#);
+        assert_eq!(server.handle("GET", "/games/2", "").status, 404);
+        assert_eq!(server.handle("POST", "/games/1/moves", "{").status, 400);
+        // would run out of stack if the parser didn't stop nesting
+        let nested = "[".repeat(64 * 1024);
+        assert_eq!(server.handle("POST", "/games/1/moves", &nested).status, 400);
+        assert_eq!(
+            server
+                .handle("POST", "/games/1/moves", r#"{"column": 7}"#)
+                .status,
+            400
+        );
+        assert_eq!(server.handle("GET", "/games/1/moves", "").status, 405);
+        for _ in 0..6 {
+            server.handle("POST", "/games/1/moves", r#"{"column": 0}"#);
+        }
+        assert_eq!(
+            server
+                .handle("POST", "/games/1/moves", r#"{"column": 0}"#)
+                .status,
+            409
+        );
+    }
+
+    #[test]
+    fn game_over() {
+        let mut server = server();
+        server.handle("POST", "/games", "");
+        for column in [0, 1, 0, 1, 0, 1, 0] {
+            server.handle(
+                "POST",
+                "/games/1/moves",
+                &format!("{{\"column\": {column}}}"),
+            );
+        }
+        let state = server.handle("GET", "/games/1", "");
+        assert_eq!(state.body.get("status"), Some(&Json::from("red_won")));
+        assert_eq!(
+            server.handle("POST", "/games/1/engine-move", "").status,
+            409
+        );
+    }
+
+    #[test]
+    fn engine_fails_to_start() {
+        let mut server = Server::new(|| Err(io::Error::other("no such bot")));
+        let created = server.handle("POST", "/games", "");
+        assert_eq!(created.status, 500);
+        assert_eq!(
+            created.body.get("error"),
+            Some(&Json::from("can't start the engine: no such bot"))
+        );
+    }
+
+    #[test]
+    fn idle_client() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            server()
+                .with_read_timeout(Duration::from_millis(100))
+                .serve_on(listener)
+        });
+
+        // connects and never says anything
+        let _idle = TcpStream::connect(address).unwrap();
+        let mut client = TcpStream::connect(address).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        client
+            .write_all(b"POST /games HTTP/1.1\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 201 Created"));
+    }
+}
@@ -0,0 +1,394 @@
+#![allow(dead_code)]
+use std::fmt::Display;
+
+use crate::{
+    board::Chip,
+    json::{FromJson, Json, JsonError, ToJson},
+};
+
+/// a range a proportion is likely in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Interval {
+    /// how many standard deviations wide a 95% interval is
+    pub const Z_95: f64 = 1.96;
+}
+
+/// the wilson score interval of `successes` out of `trials`, which unlike
+/// `p ± z * sd` stays inside 0..1 and doesn't collapse when nothing or
+/// everything succeeded
+pub fn wilson(successes: usize, trials: usize, z: f64) -> Interval {
+    if trials == 0 {
+        return Interval {
+            low: 0.0,
+            high: 1.0,
+        };
+    }
+    let (n, p) = (trials as f64, successes as f64 / trials as f64);
+    let z2 = z * z;
+    let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let margin = z / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+    Interval {
+        low: (center - margin).max(0.0),
+        high: (center + margin).min(1.0),
+    }
+}
+
+/// wins, draws and losses, from one player's point of view
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Tally {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Tally {
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    /// points per game, a draw is half a win
+    pub fn score(&self) -> f64 {
+        match self.games() {
+            0 => 0.5,
+            games => (self.wins as f64 + self.draws as f64 / 2.0) / games as f64,
+        }
+    }
+
+    /// 95% intervals of the win, draw and loss rates
+    pub fn intervals(&self) -> [Interval; 3] {
+        [self.wins, self.draws, self.losses]
+            .map(|count| wilson(count, self.games(), Interval::Z_95))
+    }
+
+    /// the same games from the other player's point of view
+    pub fn flipped(&self) -> Self {
+        Self {
+            wins: self.losses,
+            draws: self.draws,
+            losses: self.wins,
+        }
+    }
+}
+
+impl std::ops::Add for Tally {
+    type Output = Tally;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            wins: self.wins + other.wins,
+            draws: self.draws + other.draws,
+            losses: self.losses + other.losses,
+        }
+    }
+}
+
+impl Display for Tally {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let games = self.games().max(1) as f64;
+        let counts = [
+            ("wins", self.wins),
+            ("draws", self.draws),
+            ("losses", self.losses),
+        ];
+        for ((name, count), interval) in counts.into_iter().zip(self.intervals()) {
+            write!(
+                f,
+                "{name} {:.1}% ({:.1}-{:.1}), ",
+                count as f64 * 100.0 / games,
+                interval.low * 100.0,
+                interval.high * 100.0
+            )?;
+        }
+        write!(f, "{} games", self.games())
+    }
+}
+
+/// what an `Sprt` made of the games so far
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    FirstStronger,
+    SecondStronger,
+}
+
+/// a sequential probability ratio test, which after every game weighs whether
+/// the first player is `elo` stronger than the second or `elo` weaker, and
+/// stops as soon as one is much more likely than the other. `alpha` and
+/// `beta` are the odds of getting it the wrong way around
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub elo: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Self {
+            elo: 50.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+impl Sprt {
+    /// the expected score of a player `elo` stronger than its opponent
+    fn expected_score(elo: f64) -> f64 {
+        1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+    }
+
+    /// how much likelier the first player being stronger is than it being
+    /// weaker, as a log likelihood ratio. draws make the scores trinomial,
+    /// which is approximated by a normal distribution with the same variance
+    pub fn llr(&self, tally: &Tally) -> f64 {
+        if tally.games() == 0 {
+            return 0.0;
+        }
+        // a result that never happened would make the variance collapse, so
+        // those count as half a game
+        let [wins, draws, losses] =
+            [tally.wins, tally.draws, tally.losses].map(|count| (count as f64).max(0.5));
+        let games = wins + draws + losses;
+        let (wins, draws) = (wins / games, draws / games);
+        let score = wins + draws / 2.0;
+        let variance = (wins + draws / 4.0 - score * score) / games;
+        let (weaker, stronger) = (
+            Self::expected_score(-self.elo),
+            Self::expected_score(self.elo),
+        );
+        (stronger - weaker) * (2.0 * score - weaker - stronger) / (2.0 * variance)
+    }
+
+    /// the log likelihood ratios below and above which the test stops
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    pub fn verdict(&self, tally: &Tally) -> Option<Verdict> {
+        let llr = self.llr(tally);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            Some(Verdict::FirstStronger)
+        } else if llr <= lower {
+            Some(Verdict::SecondStronger)
+        } else {
+            None
+        }
+    }
+}
+
+/// the games of a match from the first player's point of view, split by the
+/// colour it played
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MatchStats {
+    pub as_red: Tally,
+    pub as_yellow: Tally,
+    /// set when an `Sprt` ended the match early
+    pub verdict: Option<Verdict>,
+}
+
+impl MatchStats {
+    /// counts one game, `first` being the colour the first player had
+    pub fn record(&mut self, first: Chip, winner: Option<Chip>) {
+        let tally = match first {
+            Chip::Red => &mut self.as_red,
+            Chip::Yellow => &mut self.as_yellow,
+        };
+        match winner {
+            None => tally.draws += 1,
+            Some(winner) if winner == first => tally.wins += 1,
+            Some(_) => tally.losses += 1,
+        }
+    }
+
+    pub fn total(&self) -> Tally {
+        self.as_red + self.as_yellow
+    }
+}
+
+impl Display for MatchStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "first: {}", self.total())?;
+        writeln!(f, "  as red: {}", self.as_red)?;
+        writeln!(f, "  as yellow: {}", self.as_yellow)?;
+        match self.verdict {
+            Some(Verdict::FirstStronger) => write!(f, "stopped early, first is stronger"),
+            Some(Verdict::SecondStronger) => write!(f, "stopped early, second is stronger"),
+            None => write!(f, "no verdict"),
+        }
+    }
+}
+
+/// the counts, and the 95% interval of every rate as `[low, high]`
+impl ToJson for Tally {
+    fn to_json(&self) -> Json {
+        let [wins, draws, losses] = self
+            .intervals()
+            .map(|interval| Json::from(vec![interval.low, interval.high]));
+        Json::object([
+            ("wins", Json::from(self.wins)),
+            ("draws", Json::from(self.draws)),
+            ("losses", Json::from(self.losses)),
+            (
+                "intervals",
+                Json::object([("wins", wins), ("draws", draws), ("losses", losses)]),
+            ),
+        ])
+    }
+}
+
+impl ToJson for MatchStats {
+    fn to_json(&self) -> Json {
+        let verdict = self.verdict.map(|verdict| match verdict {
+            Verdict::FirstStronger => "first_stronger",
+            Verdict::SecondStronger => "second_stronger",
+        });
+        Json::object([
+            ("total", self.total().to_json()),
+            ("as_red", self.as_red.to_json()),
+            ("as_yellow", self.as_yellow.to_json()),
+            ("verdict", Json::from(verdict)),
+        ])
+    }
+}
+
+impl ToJson for Sprt {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("elo", Json::from(self.elo)),
+            ("alpha", Json::from(self.alpha)),
+            ("beta", Json::from(self.beta)),
+        ])
+    }
+}
+
+/// every field is optional, `{}` is the default test
+impl FromJson for Sprt {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        let default = Sprt::default();
+        Ok(Self {
+            elo: json.field_or("elo", default.elo, Json::as_f64)?,
+            alpha: json.field_or("alpha", default.alpha, Json::as_f64)?,
+            beta: json.field_or("beta", default.beta, Json::as_f64)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        board::Chip,
+        json::{FromJson, Json, ToJson},
+    };
+
+    use super::{wilson, MatchStats, Sprt, Tally, Verdict};
+
+    fn close(left: f64, right: f64) -> bool {
+        (left - right).abs() < 1e-3
+    }
+
+    #[test]
+    fn wilson_interval() {
+        let interval = wilson(50, 100, 1.96);
+        assert!(close(interval.low, 0.4038) && close(interval.high, 0.5962));
+
+        // nothing won is still not certain to never win
+        let interval = wilson(0, 10, 1.96);
+        assert_eq!(interval.low, 0.0);
+        assert!(close(interval.high, 0.2775));
+
+        let interval = wilson(0, 0, 1.96);
+        assert_eq!((interval.low, interval.high), (0.0, 1.0));
+    }
+
+    #[test]
+    fn match_stats() {
+        let mut stats = MatchStats::default();
+        stats.record(Chip::Red, Some(Chip::Red));
+        stats.record(Chip::Yellow, Some(Chip::Red));
+        stats.record(Chip::Yellow, None);
+        assert_eq!(
+            stats.as_red,
+            Tally {
+                wins: 1,
+                draws: 0,
+                losses: 0
+            }
+        );
+        assert_eq!(
+            stats.as_yellow,
+            Tally {
+                wins: 0,
+                draws: 1,
+                losses: 1
+            }
+        );
+        assert_eq!(stats.total().score(), 0.5);
+        assert_eq!(stats.total().flipped().losses, 1);
+    }
+
+    #[test]
+    fn sprt() {
+        let sprt = Sprt::default();
+        let tally = |wins, draws, losses| Tally {
+            wins,
+            draws,
+            losses,
+        };
+        assert_eq!(sprt.verdict(&tally(0, 0, 0)), None);
+        assert_eq!(sprt.verdict(&tally(10, 5, 10)), None);
+        assert_eq!(
+            sprt.verdict(&tally(70, 10, 20)),
+            Some(Verdict::FirstStronger)
+        );
+        assert_eq!(
+            sprt.verdict(&tally(20, 10, 70)),
+            Some(Verdict::SecondStronger)
+        );
+        assert_eq!(sprt.verdict(&tally(30, 0, 0)), Some(Verdict::FirstStronger));
+        assert!(close(
+            sprt.llr(&tally(70, 10, 20)),
+            -sprt.llr(&tally(20, 10, 70))
+        ));
+
+        // an even match never gets a verdict, however long it goes
+        assert_eq!(sprt.verdict(&tally(5000, 100, 5000)), None);
+    }
+
+    #[test]
+    fn json() {
+        let sprt = Sprt::from_json(&Json::parse(r#"{"elo": 100}"#).unwrap()).unwrap();
+        assert_eq!(
+            sprt,
+            Sprt {
+                elo: 100.0,
+                ..Sprt::default()
+            }
+        );
+        assert_eq!(Sprt::from_json(&sprt.to_json()).unwrap(), sprt);
+
+        let mut stats = MatchStats::default();
+        stats.record(Chip::Red, Some(Chip::Red));
+        stats.record(Chip::Yellow, None);
+        let json = stats.to_json();
+        let as_red = json.field("as_red").unwrap();
+        assert_eq!(as_red.field_as("wins", Json::as_usize), Ok(1));
+        let interval = as_red.field("intervals").unwrap().field("wins").unwrap();
+        assert_eq!(interval.as_array().unwrap()[1].as_f64(), Some(1.0));
+        assert_eq!(
+            json.field("total")
+                .unwrap()
+                .field_as("draws", Json::as_usize),
+            Ok(1)
+        );
+        assert_eq!(json.field("verdict"), Ok(&Json::Null));
+    }
+}
@@ -0,0 +1,183 @@
+#![allow(dead_code)]
+use crate::{
+    board::{Board, Chip, ForcedMove, SearchInfo},
+    player::{Player, SearchLimits},
+};
+
+/// the column `turn` has to play no matter what: its own win, or the only
+/// column that stops the opponent's
+pub fn forced_column(board: &Board, turn: Chip) -> Option<usize> {
+    match board.forced_move(turn)? {
+        ForcedMove::Win(column) | ForcedMove::Block(column) => Some(column),
+        ForcedMove::Lost => None,
+    }
+}
+
+/// `column` if it doesn't let the opponent win on top of it, otherwise the
+/// closest column that doesn't, leaning towards the middle. when every column
+/// loses, `column` it is
+pub fn safe_column(board: &Board, turn: Chip, column: usize) -> usize {
+    let safe = board.safe_columns(turn);
+    if safe[column] {
+        return column;
+    }
+    (0..Board::COLUMN_LEN)
+        .filter(|&other| safe[other])
+        .min_by_key(|&other| {
+            (
+                other.abs_diff(column),
+                other.abs_diff(Board::COLUMN_LEN / 2),
+            )
+        })
+        .unwrap_or(column)
+}
+
+/// keeps `inner` from blundering: takes a win when there is one, blocks the
+/// opponent's, and otherwise only overrules `inner` when its column would let
+/// the opponent win right on top of it
+pub struct Tactical<P: Player> {
+    inner: P,
+}
+
+impl<P: Player> Tactical<P> {
+    pub fn new(inner: P) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&mut self) -> &mut P {
+        &mut self.inner
+    }
+}
+
+impl<P: Player> Player for Tactical<P> {
+    fn choose_column(&mut self, board: Board, turn: Chip) -> usize {
+        self.choose_with_score(board, turn).0
+    }
+
+    fn choose_with_score(&mut self, board: Board, turn: Chip) -> (usize, Option<i16>) {
+        if let Some(column) = forced_column(&board, turn) {
+            return (column, None);
+        }
+        let (column, score) = self.inner.choose_with_score(board, turn);
+        match safe_column(&board, turn, column) {
+            safe if safe == column => (column, score),
+            safe => (safe, None),
+        }
+    }
+
+    fn search(
+        &mut self,
+        board: Board,
+        turn: Chip,
+        limits: SearchLimits,
+        report: &mut dyn FnMut(&SearchInfo),
+    ) -> (usize, Option<i16>) {
+        if let Some(column) = forced_column(&board, turn) {
+            return (column, None);
+        }
+        let (column, score) = self.inner.search(board, turn, limits, report);
+        match safe_column(&board, turn, column) {
+            safe if safe == column => (column, score),
+            safe => (safe, None),
+        }
+    }
+
+    fn hint(&mut self, board: Board, turn: Chip) -> (usize, Option<i16>) {
+        if let Some(column) = forced_column(&board, turn) {
+            return (column, None);
+        }
+        let (column, score) = self.inner.hint(board, turn);
+        match safe_column(&board, turn, column) {
+            safe if safe == column => (column, score),
+            safe => (safe, None),
+        }
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> bool {
+        self.inner.set_option(name, value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        board::{Board, Chip},
+        bot::Bot,
+        neural::{Network, NeuralPlayer},
+        player::{player_from_spec, MinmaxPlayer, Player},
+        rand::Rand,
+    };
+
+    use super::{safe_column, Tactical};
+
+    enum Expect {
+        Play(usize),
+        Avoid(usize),
+    }
+
+    /// positions every player has to get right once wrapped, as the moves
+    /// leading to them
+    const POSITIONS: &[(&str, Expect)] = &[
+        // red has three on the bottom row
+        ("001122", Expect::Play(3)),
+        ("00112", Expect::Play(3)),
+        // and three stacked in the first column
+        ("010101", Expect::Play(0)),
+        ("01010", Expect::Play(0)),
+        // yellow can both win and block, winning comes first
+        ("0101012", Expect::Play(1)),
+        // red has three on the second row, yellow must not fill the cell under
+        // the fourth
+        ("1002261", Expect::Avoid(3)),
+        // and the same with the colours swapped
+        ("01206261", Expect::Avoid(3)),
+        // a diagonal for red, with the last cell right above column 3
+        ("310212236", Expect::Avoid(3)),
+    ];
+
+    fn check(player: &mut dyn Player, name: &str) {
+        for (moves, expect) in POSITIONS {
+            let board = Board::from_moves(moves).unwrap();
+            let column = player.choose_column(board, board.turn());
+            match expect {
+                Expect::Play(expected) => {
+                    assert_eq!(column, *expected, "{name} on {moves}")
+                }
+                Expect::Avoid(avoided) => assert_ne!(column, *avoided, "{name} on {moves}"),
+            }
+        }
+    }
+
+    #[test]
+    fn positions() {
+        // minmax sees all of these on its own from depth 1
+        check(&mut MinmaxPlayer::new(1), "minmax");
+        check(&mut Tactical::new(MinmaxPlayer::new(0)), "minmax");
+        for seed in 0..20 {
+            check(&mut Tactical::new(Bot::new(50, seed)), "bot");
+        }
+        let network = Network::new(8, &mut Rand::new(3));
+        check(&mut Tactical::new(NeuralPlayer::new(network, None)), "net");
+        check(
+            player_from_spec("safe:minmax:0").unwrap().as_mut(),
+            "safe:minmax:0",
+        );
+    }
+
+    #[test]
+    fn closest_safe_column() {
+        let board = Board::from_moves("1002261").unwrap();
+        assert_eq!(
+            board.safe_columns(Chip::Yellow),
+            [true, true, true, false, true, true, true]
+        );
+        assert_eq!(safe_column(&board, Chip::Yellow, 3), 2);
+        assert_eq!(safe_column(&board, Chip::Yellow, 5), 5);
+
+        // yellow has both ends of its three open, red is lost either way, so
+        // the column stays
+        let board = Board::from_moves("020364").unwrap();
+        assert_eq!(board.safe_columns(Chip::Red), [false; 7]);
+        assert_eq!(safe_column(&board, Chip::Red, 6), 6);
+    }
+}
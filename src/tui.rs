@@ -0,0 +1,389 @@
+#![allow(dead_code)]
+use std::{
+    fs::File,
+    io::{self, IsTerminal, Read, Write},
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    board::{Board, Chip, Minmaxxing},
+    interactive::Participant,
+    player::Player,
+};
+
+/// puts the terminal in raw mode for as long as it lives
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn stty(args: &[&str]) -> io::Result<String> {
+        let output = Command::new("stty")
+            .args(args)
+            .stdin(File::open("/dev/tty")?)
+            .stderr(Stdio::inherit())
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::other("stty failed"));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn enable() -> io::Result<Self> {
+        let saved = Self::stty(&["-g"])?;
+        Self::stty(&["raw", "-echo"])?;
+        print!("\x1b[?25l");
+        Ok(Self { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\r\n");
+        let _ = io::stdout().flush();
+        let _ = Self::stty(&[&self.saved]);
+    }
+}
+
+enum Key {
+    Left,
+    Right,
+    Drop,
+    Column(usize),
+    Hint,
+    Undo,
+    Threats,
+    Quit,
+    Other,
+}
+
+fn read_key(stdin: &mut impl Read) -> Key {
+    let mut byte = [0];
+    if stdin.read(&mut byte).unwrap_or(0) == 0 {
+        return Key::Quit;
+    }
+    match byte[0] {
+        b'\x1b' => {
+            let mut sequence = [0; 2];
+            if stdin.read_exact(&mut sequence).is_err() {
+                return Key::Other;
+            }
+            match sequence {
+                [b'[', b'D'] => Key::Left,
+                [b'[', b'C'] => Key::Right,
+                _ => Key::Other,
+            }
+        }
+        b'a' => Key::Left,
+        b'd' => Key::Right,
+        b'\r' | b'\n' | b' ' | b's' => Key::Drop,
+        digit @ b'0'..=b'6' => Key::Column((digit - b'0') as usize),
+        b'h' => Key::Hint,
+        b'u' => Key::Undo,
+        b't' => Key::Threats,
+        b'q' | 0x03 | 0x04 => Key::Quit,
+        _ => Key::Other,
+    }
+}
+
+/// a full-screen game, played with the arrow keys
+pub struct TuiGame {
+    board: Board,
+    turn: Chip,
+    history: Vec<usize>,
+    cursor: usize,
+    eval_depth: u8,
+    /// minmax's column and score for the board, searched once per move
+    eval: Option<(usize, i16)>,
+    show_threats: bool,
+    status: String,
+}
+
+impl TuiGame {
+    const FRAME_DELAY: Duration = Duration::from_millis(35);
+    const SPECTATOR_DELAY: Duration = Duration::from_millis(400);
+
+    pub fn new() -> Self {
+        Self {
+            board: Board::new(),
+            turn: Chip::Red,
+            history: Vec::new(),
+            cursor: Board::COLUMN_LEN / 2,
+            eval_depth: 4,
+            eval: None,
+            show_threats: false,
+            status: String::new(),
+        }
+    }
+
+    /// how deep minmax searches for the evaluation panel and hints
+    pub fn with_eval_depth(mut self, eval_depth: u8) -> Self {
+        self.eval_depth = eval_depth;
+        self
+    }
+
+    /// whether both ends of the terminal can handle the full-screen ui
+    pub fn supported() -> bool {
+        io::stdout().is_terminal() && io::stdin().is_terminal()
+    }
+
+    fn evaluate(&mut self) {
+        self.eval = match self
+            .board
+            .minmax_with_depth(self.turn, self.turn, self.eval_depth)
+        {
+            Minmaxxing::Position(column, score) => Some((column, score)),
+            Minmaxxing::Result(_) => None,
+        };
+    }
+
+    fn render(&self, falling: Option<(usize, usize, Chip)>) -> String {
+        let threats = if self.show_threats {
+            self.board.threat_marks()
+        } else {
+            Vec::new()
+        };
+
+        let mut left = Vec::new();
+        left.push(format!(" {:?} to move", self.turn));
+        left.push(String::new());
+        let cursor: [_; Board::COLUMN_LEN] = std::array::from_fn(|column| {
+            if column == self.cursor && falling.is_none() {
+                match self.turn {
+                    Chip::Red => "\x1b[0;31mv\x1b[0m",
+                    Chip::Yellow => "\x1b[0;33mv\x1b[0m",
+                }
+            } else {
+                " "
+            }
+        });
+        left.push(format!(" {} ", cursor.join(" ")));
+        let board = self.board.with_marks(&threats).with_falling(falling);
+        left.extend(board.to_string().lines().map(str::to_string));
+
+        let mut right = vec!["Moves".to_string()];
+        let moves: Vec<_> = self
+            .history
+            .chunks(2)
+            .enumerate()
+            .map(|(idx, pair)| match pair {
+                [red, yellow] => format!("{:>2}. {red} {yellow}", idx + 1),
+                [red] => format!("{:>2}. {red}", idx + 1),
+                _ => unreachable!("chunks of 2"),
+            })
+            .collect();
+        let skip = moves.len().saturating_sub(5);
+        right.extend(moves.into_iter().skip(skip));
+        while right.len() < 7 {
+            right.push(String::new());
+        }
+        right.push(format!("Eval for {:?}", self.turn));
+        right.push(format!(
+            "  board value: {}",
+            self.board.value_of_board(self.turn)
+        ));
+        if let (Some((column, score)), None) = (self.eval, falling) {
+            right.push(format!(
+                "  minmax {}: '{column}' ({score})",
+                self.eval_depth
+            ));
+        }
+
+        // the board is always 15 columns wide, excluding escape codes
+        let left_width = 2 * Board::COLUMN_LEN + 1;
+        let mut lines = Vec::new();
+        for idx in 0..left.len().max(right.len()) {
+            let left = left.get(idx).map(String::as_str).unwrap_or("");
+            let right = right.get(idx).map(String::as_str).unwrap_or("");
+            let padding = if idx < 2 {
+                (left_width + 4).saturating_sub(left.len())
+            } else if left.is_empty() {
+                left_width + 4
+            } else {
+                4
+            };
+            lines.push(format!("{left}{}{right}", " ".repeat(padding)));
+        }
+        lines.push(String::new());
+        lines.push(self.status.clone());
+        lines.push(String::new());
+        lines.push("arrows/a/d move  enter drop  0-6 drop in column".to_string());
+        lines.push("h hint  u undo  t threats  q quit".to_string());
+
+        format!("\x1b[2J\x1b[H{}", lines.join("\r\n"))
+    }
+
+    fn draw(&self, falling: Option<(usize, usize, Chip)>) {
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(self.render(falling).as_bytes());
+        let _ = stdout.flush();
+    }
+
+    fn animate_drop(&self, column: usize, row: usize) {
+        for falling_row in (row + 1..Board::ROW_LEN).rev() {
+            self.draw(Some((column, falling_row, self.turn)));
+            thread::sleep(Self::FRAME_DELAY);
+        }
+    }
+
+    fn undo(&mut self, plies: usize) {
+        if self.history.len() < plies {
+            self.status = "Nothing to undo".to_string();
+            return;
+        }
+        self.history.truncate(self.history.len() - plies);
+        self.board = Board::new();
+        for &column in &self.history {
+            self.board
+                .place_chip(column, self.board.turn())
+                .expect("replaying moves that were already played");
+        }
+        self.turn = self.board.turn();
+        self.evaluate();
+        self.status = "Took back your last move".to_string();
+    }
+
+    /// waits for the human to drop a chip in an available column. hints come
+    /// from `engine`, the one the human plays against, if there is one.
+    /// `None` if the human quit
+    fn human_turn(
+        &mut self,
+        stdin: &mut impl Read,
+        undo_plies: usize,
+        mut engine: Option<&mut dyn Player>,
+    ) -> Option<usize> {
+        loop {
+            self.draw(None);
+            match read_key(stdin) {
+                Key::Left => self.cursor = self.cursor.saturating_sub(1),
+                Key::Right => self.cursor = (self.cursor + 1).min(Board::COLUMN_LEN - 1),
+                Key::Column(column) => {
+                    self.cursor = column;
+                    if self.board.available_column_choices()[column] {
+                        break Some(column);
+                    }
+                    self.status = format!("Column '{column}' is full, pick another column");
+                }
+                Key::Drop => {
+                    if self.board.available_column_choices()[self.cursor] {
+                        break Some(self.cursor);
+                    }
+                    self.status = format!("Column '{}' is full, pick another column", self.cursor);
+                }
+                Key::Hint => match engine.as_mut() {
+                    Some(engine) => {
+                        let (column, score) = engine.hint(self.board, self.turn);
+                        self.cursor = column;
+                        self.status = match score {
+                            Some(score) => {
+                                format!("The bot suggests '{column}' with score '{score}'")
+                            }
+                            None => format!("The bot suggests '{column}'"),
+                        };
+                    }
+                    None => {
+                        if let Some((column, score)) = self.eval {
+                            self.cursor = column;
+                            self.status =
+                                format!("Minmax suggests '{column}' with score '{score}'");
+                        }
+                    }
+                },
+                Key::Undo => self.undo(undo_plies),
+                Key::Threats => self.show_threats = !self.show_threats,
+                Key::Quit => break None,
+                Key::Other => (),
+            }
+        }
+    }
+
+    /// fails without playing if the terminal can't be put in raw mode, so the
+    /// participants can still play somewhere else
+    pub fn play<'engine>(
+        mut self,
+        red: &mut Participant<'engine>,
+        yellow: &mut Participant<'engine>,
+    ) -> io::Result<()> {
+        let _raw_mode = RawMode::enable()?;
+        let mut stdin = io::stdin().lock();
+        self.evaluate();
+
+        let humans = [&*red, &*yellow]
+            .into_iter()
+            .filter(|participant| matches!(participant, Participant::Human))
+            .count();
+        let undo_plies = if humans == 1 { 2 } else { 1 };
+
+        loop {
+            let (participant, opponent) = match self.turn {
+                Chip::Red => (&mut *red, &mut *yellow),
+                Chip::Yellow => (&mut *yellow, &mut *red),
+            };
+            let column = match participant {
+                Participant::Human => {
+                    let engine = match opponent {
+                        Participant::Engine(engine) => Some(&mut **engine as &mut dyn Player),
+                        Participant::Human => None,
+                    };
+                    let Some(column) = self.human_turn(&mut stdin, undo_plies, engine) else {
+                        return Ok(());
+                    };
+                    self.status = String::new();
+                    column
+                }
+                Participant::Engine(engine) => {
+                    self.status = format!("The bot ({:?}) is thinking...", self.turn);
+                    self.draw(None);
+                    if humans == 0 {
+                        thread::sleep(Self::SPECTATOR_DELAY);
+                    }
+                    let (column, score) = engine.choose_with_score(self.board, self.turn);
+                    self.status = match score {
+                        Some(score) => format!(
+                            "The bot ({:?}) chose '{column}' with score '{score}'",
+                            self.turn
+                        ),
+                        None => format!("The bot ({:?}) chose '{column}'", self.turn),
+                    };
+                    column
+                }
+            };
+            // the chip only lands once it's done falling
+            let mut placed = self.board;
+            let Ok(placed_row) = placed.place_chip(column, self.turn) else {
+                self.status = format!("The bot ({:?}) made an invalid move, stopping", self.turn);
+                break;
+            };
+            self.cursor = column;
+            self.animate_drop(column, placed_row);
+            self.board = placed;
+            self.history.push(column);
+
+            if let Some(winner) = self.board.winner(column, placed_row) {
+                let (winner_participant, loser) = match winner {
+                    Chip::Red => (&*red, &*yellow),
+                    Chip::Yellow => (&*yellow, &*red),
+                };
+                self.status = match (winner_participant, loser) {
+                    (Participant::Human, Participant::Engine(_)) => "Player won!".to_string(),
+                    (Participant::Engine(_), Participant::Human) => "Bot won!".to_string(),
+                    _ => format!("{winner:?} won!"),
+                };
+                break;
+            }
+            self.turn = self.turn.opposite();
+            if self.board.filled() {
+                self.status = "Tied!".to_string();
+                break;
+            }
+            self.evaluate();
+        }
+
+        self.status.push_str(" Press any key to exit");
+        self.draw(None);
+        let _ = read_key(&mut stdin);
+        Ok(())
+    }
+}